@@ -0,0 +1,255 @@
+//! Multi-Backend Comparison Benchmarks
+//!
+//! Runs the same scenarios against every backend compiled into the build, each used directly
+//! through [`BackendAllocator`] instead of whichever allocator the crate selected globally.
+//!
+//! ## Test Scenarios
+//!
+//! 1. **small** - Allocate/free pairs of 16 to 512 bytes with a sliding window of live blocks
+//! 2. **large** - Allocate/free pairs of 64 KiB to 1 MiB
+//! 3. **growth** - Vector-style growth through repeated reallocation
+//! 4. **fragmentation** - Mixed sizes freed out of order
+//! 5. **multi_thread** - Small allocate/free pairs on several threads at once
+//! 6. **cross_thread** - Blocks allocated on one thread and freed on another
+//!
+//! ## Usage
+//!
+//! ```bash
+//! # Compare system and mimalloc
+//! cargo bench --bench backend_comparison
+//!
+//! # Compare system and mimalloc-secure (mimalloc is built either regular or secure)
+//! cargo bench --bench backend_comparison --features secure
+//!
+//! # Only run scenarios whose name contains "thread"
+//! cargo bench --bench backend_comparison -- thread
+//! ```
+//!
+//! ## Output
+//!
+//! A table of nanoseconds per operation (median of several samples, lower is better) with each
+//! backend relative to the system allocator, and a JSON file for regression tracking written to
+//! `target/backend-comparison.json` (`BACKEND_COMPARISON_JSON` overrides the path).
+
+use auto_allocator::BackendAllocator;
+use std::alloc::{GlobalAlloc, Layout};
+use std::fmt::Write as _;
+use std::hint::black_box;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+/// Timed runs per scenario and backend; the median is reported
+const SAMPLES: usize = 11;
+
+/// Threads used by the multi-threaded scenarios
+const THREADS: usize = 4;
+
+struct Scenario {
+    name: &'static str,
+    /// Operations performed by one run, used to report time per operation
+    ops: usize,
+    run: fn(BackendAllocator),
+}
+
+const SCENARIOS: [Scenario; 6] = [
+    Scenario { name: "small", ops: 20_000, run: small },
+    Scenario { name: "large", ops: 200, run: large },
+    Scenario { name: "growth", ops: 64 * 16, run: growth },
+    Scenario { name: "fragmentation", ops: 8_192, run: fragmentation },
+    Scenario { name: "multi_thread", ops: THREADS * 10_000, run: multi_thread },
+    Scenario { name: "cross_thread", ops: 16 * 512, run: cross_thread },
+];
+
+fn layout(size: usize, align: usize) -> Layout {
+    Layout::from_size_align(size, align).unwrap()
+}
+
+/// Allocates a block and writes to it, so the backend cannot hand out untouched memory for free
+unsafe fn alloc_touched(backend: BackendAllocator, layout: Layout) -> *mut u8 {
+    let ptr = black_box(backend.alloc(layout));
+    assert!(!ptr.is_null(), "{} failed to allocate {:?}", backend.name(), layout);
+    ptr.write(1);
+    ptr
+}
+
+fn small(backend: BackendAllocator) {
+    small_pairs(backend, 20_000);
+}
+
+fn small_pairs(backend: BackendAllocator, ops: usize) {
+    let mut live = [(std::ptr::null_mut::<u8>(), layout(1, 1)); 64];
+    for op in 0..ops {
+        let slot = &mut live[op % 64];
+        unsafe {
+            if !slot.0.is_null() {
+                backend.dealloc(slot.0, slot.1);
+            }
+            let block = layout(16 << (op % 6), 8);
+            *slot = (alloc_touched(backend, block), block);
+        }
+    }
+    for (ptr, block) in live {
+        if !ptr.is_null() {
+            unsafe { backend.dealloc(ptr, block) };
+        }
+    }
+}
+
+fn large(backend: BackendAllocator) {
+    for op in 0..200 {
+        let block = layout((64 * 1024) << (op % 5), 16);
+        unsafe {
+            let ptr = alloc_touched(backend, block);
+            backend.dealloc(ptr, block);
+        }
+    }
+}
+
+fn growth(backend: BackendAllocator) {
+    for _ in 0..64 {
+        let mut block = layout(16, 8);
+        let mut ptr = unsafe { alloc_touched(backend, block) };
+        for _ in 1..16 {
+            let new_size = block.size() * 2;
+            ptr = unsafe { black_box(backend.realloc(ptr, block, new_size)) };
+            assert!(!ptr.is_null());
+            block = layout(new_size, 8);
+        }
+        unsafe { backend.dealloc(ptr, block) };
+    }
+}
+
+fn fragmentation(backend: BackendAllocator) {
+    const SIZES: [usize; 8] = [24, 4096, 72, 640, 16, 32_768, 200, 1500];
+    let mut blocks = Vec::with_capacity(4_096);
+    for i in 0..4_096 {
+        let block = layout(SIZES[i % SIZES.len()], 8);
+        blocks.push((unsafe { alloc_touched(backend, block) }, block));
+    }
+    // Free every other block first, then the rest, leaving holes between survivors
+    for (ptr, block) in blocks.iter().step_by(2).chain(blocks.iter().skip(1).step_by(2)) {
+        unsafe { backend.dealloc(*ptr, *block) };
+    }
+}
+
+fn multi_thread(backend: BackendAllocator) {
+    thread::scope(|scope| {
+        for _ in 0..THREADS {
+            scope.spawn(move || small_pairs(backend, 10_000));
+        }
+    });
+}
+
+fn cross_thread(backend: BackendAllocator) {
+    let block = layout(64, 8);
+    let (sender, receiver) = mpsc::sync_channel::<Vec<usize>>(2);
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            for batch in receiver {
+                for ptr in batch {
+                    unsafe { backend.dealloc(ptr as *mut u8, block) };
+                }
+            }
+        });
+        for _ in 0..16 {
+            let batch = (0..512).map(|_| unsafe { alloc_touched(backend, block) } as usize).collect();
+            sender.send(batch).unwrap();
+        }
+        drop(sender);
+    });
+}
+
+/// Median nanoseconds per operation over `SAMPLES` runs, after one untimed warm-up run
+fn measure(scenario: &Scenario, backend: BackendAllocator) -> f64 {
+    (scenario.run)(backend);
+    let mut samples: Vec<f64> = (0..SAMPLES)
+        .map(|_| {
+            let started = Instant::now();
+            (scenario.run)(backend);
+            started.elapsed().as_nanos() as f64 / scenario.ops as f64
+        })
+        .collect();
+    samples.sort_by(f64::total_cmp);
+    samples[SAMPLES / 2]
+}
+
+fn json_path() -> std::path::PathBuf {
+    if let Some(path) = std::env::var_os("BACKEND_COMPARISON_JSON") {
+        return path.into();
+    }
+    let target = std::env::var_os("CARGO_TARGET_DIR").unwrap_or_else(|| "target".into());
+    std::path::Path::new(&target).join("backend-comparison.json")
+}
+
+fn main() {
+    // cargo bench passes flags such as --bench; any other argument filters scenarios by name
+    let filter = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let backends = BackendAllocator::available();
+    let scenarios: Vec<&Scenario> = SCENARIOS
+        .iter()
+        .filter(|scenario| match &filter {
+            Some(filter) => scenario.name.contains(filter.as_str()),
+            None => true,
+        })
+        .collect();
+
+    let info = auto_allocator::get_allocator_info();
+    println!(
+        "Comparing {} backends on {} cores, {} memory (global allocator: {:?})",
+        backends.len(),
+        info.system_info.cpu_cores,
+        auto_allocator::format_memory_size(info.system_info.total_memory_bytes),
+        info.allocator_type
+    );
+    println!();
+
+    let mut header = format!("{:<16}", "scenario (ns/op)");
+    for backend in &backends {
+        let _ = write!(header, " {:>24}", backend.name());
+    }
+    println!("{}", header);
+
+    let mut results = Vec::new();
+    for scenario in scenarios {
+        let timings: Vec<f64> = backends.iter().map(|&backend| measure(scenario, backend)).collect();
+        let mut row = format!("{:<16}", scenario.name);
+        for (backend, &ns) in backends.iter().zip(&timings) {
+            let _ = write!(row, " {:>13.1} ({:>6.2}x)", ns, timings[0] / ns);
+            results.push((scenario, *backend, ns));
+        }
+        println!("{}", row);
+    }
+    println!();
+    println!("(Nx) is the speed relative to the system allocator, higher is faster");
+
+    let mut json = format!(
+        "{{\n  \"crate_version\": \"{}\",\n  \"cpu_cores\": {},\n  \"total_memory_bytes\": {},\n  \"samples\": {},\n  \"results\": [",
+        env!("CARGO_PKG_VERSION"),
+        info.system_info.cpu_cores,
+        info.system_info.total_memory_bytes,
+        SAMPLES
+    );
+    for (index, (scenario, backend, ns)) in results.iter().enumerate() {
+        let separator = if index == 0 { "" } else { "," };
+        let _ = write!(
+            json,
+            "{}\n    {{\"scenario\": \"{}\", \"backend\": \"{}\", \"ops\": {}, \"ns_per_op\": {:.2}}}",
+            separator,
+            scenario.name,
+            backend.name(),
+            scenario.ops,
+            ns
+        );
+    }
+    json.push_str("\n  ]\n}\n");
+
+    let path = json_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match std::fs::write(&path, json) {
+        Ok(()) => println!("JSON results written to {}", path.display()),
+        Err(err) => eprintln!("Failed to write {}: {}", path.display(), err),
+    }
+}
@@ -7,11 +7,32 @@ use std::process::Command;
 /// - Checks if mimalloc can compile (GCC version, stdatomic.h availability)
 /// - Stops compilation on incompatible systems with clear error messages
 /// - Provides upgrade guidance for legacy systems
-
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
     
     validate_platform_compatibility();
+    report_sanitizers();
+    report_forced_backend();
+}
+
+/// Sets `auto_allocator_forced` when a `force-*` feature fixes the backend, so tests that
+/// exercise runtime selection can skip themselves
+fn report_forced_backend() {
+    let forced = ["CARGO_FEATURE_FORCE_SYSTEM", "CARGO_FEATURE_FORCE_MIMALLOC", "CARGO_FEATURE_FORCE_MIMALLOC_SECURE"]
+        .iter()
+        .any(|feature| env::var_os(feature).is_some());
+    if forced {
+        println!("cargo:rustc-cfg=auto_allocator_forced");
+    }
+}
+
+/// Passes `-Zsanitizer` settings to the crate as `auto_allocator_sanitize` cfgs, since
+/// `cfg(sanitize)` itself is unstable
+fn report_sanitizers() {
+    let sanitizers = env::var("CARGO_CFG_SANITIZE").unwrap_or_default();
+    for sanitizer in sanitizers.split(',').filter(|sanitizer| !sanitizer.is_empty()) {
+        println!("cargo:rustc-cfg=auto_allocator_sanitize=\"{}\"", sanitizer);
+    }
 }
 
 /// Validates that the current platform can compile mimalloc
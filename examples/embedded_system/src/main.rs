@@ -123,6 +123,12 @@ pub extern "C" fn _start() -> ! {
         auto_allocator::AllocatorType::MimallocSecure => {
             print_str(b"MimallocSecure (ERROR: not available in no_std!) [ERROR]\n")
         },
+        auto_allocator::AllocatorType::DebugGuard => {
+            print_str(b"DebugGuard (ERROR: not available in no_std!) [ERROR]\n")
+        },
+        _ => {
+            print_str(b"Unknown (ERROR: should be embedded!) [ERROR]\n")
+        },
     }
     
     print_str(b"Selection Reason: ");
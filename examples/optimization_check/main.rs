@@ -83,6 +83,12 @@ fn main() {
             println!("   • Memory-constrained applications");
             println!("   • Real-time systems requiring deterministic allocation");
         }
+        auto_allocator::AllocatorType::DebugGuard => {
+            println!("debug-guard allocator is only used when requested:");
+            println!("   • Hunting heap overflows in unsafe code and FFI");
+            println!("   • Selected with AUTO_ALLOCATOR_BACKEND=debug-guard");
+        }
+        _ => println!("{:?} is recommended", recommended_type),
     }
 
     println!();
@@ -5,7 +5,6 @@
 /// 2. 📊 System information viewing
 /// 3. ⚙️ Environment variable control methods
 /// 4. 🧪 Basic memory allocation testing
-
 // This is the core usage of auto-allocator: just one use statement enables automatic allocator selection
 #[allow(clippy::single_component_path_imports)]
 use auto_allocator;
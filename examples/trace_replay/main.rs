@@ -0,0 +1,85 @@
+/// Replays an allocation trace against every available backend
+///
+/// Record a trace of a real workload by running it with `AUTO_ALLOCATOR_TRACE=<path>` (or by
+/// calling `auto_allocator::start_trace`), then pass the path here. Without a path, a small
+/// demo workload is recorded first.
+use auto_allocator::{replay_trace, AllocatorType};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+const BACKENDS: [(&str, AllocatorType); 3] = [
+    ("system", AllocatorType::System),
+    ("mimalloc", AllocatorType::Mimalloc),
+    ("mimalloc-secure", AllocatorType::MimallocSecure),
+];
+
+/// Mix of short-lived buffers, a growing map and strings that are resized in place
+fn demo_workload() {
+    let mut map: HashMap<u64, Vec<u8>> = HashMap::new();
+    for i in 0..20_000u64 {
+        let scratch = vec![0u8; (i % 512) as usize + 16];
+        map.insert(i % 4096, scratch.repeat(2));
+        let mut text = String::new();
+        for _ in 0..(i % 8) {
+            text.push_str("allocation trace ");
+        }
+        std::hint::black_box(&text);
+    }
+}
+
+/// Replays the trace against one backend and prints a row; runs in its own process so the
+/// peak RSS belongs to that backend alone
+fn replay_one(path: &str, name: &str) {
+    let (_, allocator_type) = BACKENDS.iter().find(|(backend, _)| *backend == name).unwrap();
+    match replay_trace(path, *allocator_type) {
+        Ok(report) => println!(
+            "{:<16} {:>10} {:>12.2?} {:>12} {:>12} {:>8.1}%",
+            name,
+            report.events,
+            report.elapsed,
+            auto_allocator::format_memory_size(report.peak_live_bytes),
+            auto_allocator::format_memory_size(report.peak_rss_bytes),
+            report.fragmentation * 100.0
+        ),
+        Err(err) if err.kind() == std::io::ErrorKind::Unsupported => {
+            println!("{:<16} not available in this build", name)
+        }
+        Err(err) => println!("{:<16} replay failed: {}", name, err),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if let [_, path, flag, name] = args.as_slice() {
+        if flag == "--backend" {
+            replay_one(path, name);
+            return;
+        }
+    }
+
+    let path = match args.get(1) {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let path = std::env::temp_dir().join("auto-allocator-demo.trace");
+            auto_allocator::start_trace(&path).expect("failed to start trace");
+            demo_workload();
+            let summary = auto_allocator::stop_trace().expect("failed to write trace");
+            println!("Recorded demo workload: {} events, {} bytes", summary.events, summary.bytes);
+            path
+        }
+    };
+
+    println!("=== Replaying {} ===", path.display());
+    println!(
+        "{:<16} {:>10} {:>12} {:>12} {:>12} {:>9}",
+        "backend", "events", "time", "peak live", "peak RSS", "frag"
+    );
+    let exe = std::env::current_exe().expect("failed to locate this example");
+    for (name, _) in BACKENDS {
+        let status = Command::new(&exe).arg(&path).arg("--backend").arg(name).status();
+        if !matches!(status, Ok(status) if status.success()) {
+            println!("{:<16} replay process failed", name);
+        }
+    }
+}
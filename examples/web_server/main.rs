@@ -81,6 +81,10 @@ fn main() {
         auto_allocator::AllocatorType::EmbeddedHeap => {
             println!("embedded allocator automatically selected - optimized for constrained environments!");
         }
+        auto_allocator::AllocatorType::DebugGuard => {
+            println!("debug-guard selected by override - guard pages catch heap overflows (debugging only)");
+        }
+        _ => println!("{:?} allocator selected", info.allocator_type),
     }
 }
 
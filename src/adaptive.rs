@@ -0,0 +1,410 @@
+use core::alloc::Layout;
+use core::hint::black_box;
+use core::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use crate::platform::{get_cpu_cores_safe, selectable_mimalloc, RUNTIME_ALLOCATOR_ID};
+use crate::runtime::RuntimeAllocator;
+use crate::types::{AllocatorType, BackendTiming, Calibration};
+// ========== Adaptive Selection ==========
+
+/// System allocator plus at most one mimalloc build
+const MAX_CANDIDATES: usize = 2;
+
+/// Small allocate/free pairs on one thread, cycling through sizes from 16 to 512 bytes
+const SMALL_OPS: usize = 10_000;
+
+/// Small blocks kept alive at once, so the allocator sees interleaved lifetimes
+const SMALL_WINDOW: usize = 64;
+
+/// Large allocate/free pairs, cycling through sizes from 64 KiB to 1 MiB
+const LARGE_OPS: usize = 64;
+
+/// Small allocate/free pairs per thread in the multi-threaded workload
+const THREAD_OPS: usize = 5_000;
+
+/// Upper bound on calibration threads, keeping startup cost flat on large machines
+const MAX_THREADS: usize = 4;
+
+/// Batches handed from the allocating thread to the freeing thread
+const CROSS_ROUNDS: usize = 16;
+
+/// Blocks per batch in the cross-thread workload
+const CROSS_BATCH: usize = 256;
+
+/// Raw timings of one candidate; `u64::MAX` marks a workload that failed
+#[derive(Clone, Copy)]
+struct Measured {
+    allocator_id: u8,
+    small_ns: u64,
+    large_ns: u64,
+    multi_thread_ns: u64,
+    cross_thread_ns: u64,
+}
+
+impl Measured {
+    const EMPTY: Measured = Measured {
+        allocator_id: 0,
+        small_ns: 0,
+        large_ns: 0,
+        multi_thread_ns: 0,
+        cross_thread_ns: 0,
+    };
+
+    fn timing(&self) -> BackendTiming {
+        BackendTiming {
+            allocator_type: AllocatorType::from_id(self.allocator_id),
+            small_ns: self.small_ns,
+            large_ns: self.large_ns,
+            multi_thread_ns: self.multi_thread_ns,
+            cross_thread_ns: self.cross_thread_ns,
+        }
+    }
+}
+
+/// Calibration results, kept in a fixed array because they are recorded inside the allocator
+struct Results {
+    measured: [Measured; MAX_CANDIDATES],
+    count: usize,
+    threads: usize,
+    elapsed: Duration,
+}
+
+static RESULTS: Mutex<Option<Results>> = Mutex::new(None);
+static CALIBRATING: AtomicBool = AtomicBool::new(false);
+
+fn results() -> MutexGuard<'static, Option<Results>> {
+    RESULTS.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Returns the measurements behind the selection, if the calibration ran
+pub(crate) fn calibration() -> Option<Calibration> {
+    let results = results();
+    let results = results.as_ref()?;
+    let mut timings: Vec<BackendTiming> =
+        results.measured[..results.count].iter().map(Measured::timing).collect();
+    timings.sort_by_key(BackendTiming::total_ns);
+    Some(Calibration { timings, threads: results.threads, elapsed: results.elapsed })
+}
+
+/// Whether the selection was made by calibration
+pub(crate) fn is_calibrated() -> bool {
+    results().is_some()
+}
+
+// ========== Persisted Measurements ==========
+
+/// Appends the measurements to a selection cache entry, as `threads=<n> elapsed_ns=<n>`
+/// followed by one `timing=<id>:<small>:<large>:<multi>:<cross>` per candidate
+#[cfg(feature = "selection-cache")]
+pub(crate) fn write_record(out: &mut impl core::fmt::Write) -> core::fmt::Result {
+    let results = results();
+    let Some(results) = results.as_ref() else {
+        return Ok(());
+    };
+    write!(out, " threads={} elapsed_ns={}", results.threads, results.elapsed.as_nanos() as u64)?;
+    for measured in &results.measured[..results.count] {
+        write!(
+            out,
+            " timing={}:{}:{}:{}:{}",
+            measured.allocator_id,
+            measured.small_ns,
+            measured.large_ns,
+            measured.multi_thread_ns,
+            measured.cross_thread_ns
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "selection-cache")]
+fn parse_u64(digits: &[u8]) -> Option<u64> {
+    core::str::from_utf8(digits).ok()?.parse().ok()
+}
+
+#[cfg(feature = "selection-cache")]
+fn parse_timing(value: &[u8]) -> Option<Measured> {
+    let mut parts = value.split(|&b| b == b':').map(parse_u64);
+    let mut next = || parts.next().flatten();
+    let measured = Measured {
+        allocator_id: u8::try_from(next()?).ok()?,
+        small_ns: next()?,
+        large_ns: next()?,
+        multi_thread_ns: next()?,
+        cross_thread_ns: next()?,
+    };
+    parts.next().is_none().then_some(measured)
+}
+
+/// Restores the measurements saved with a cached decision, so a run that reuses it still
+/// reports the calibration behind it; entries without measurements restore nothing
+#[cfg(feature = "selection-cache")]
+pub(crate) fn restore_record(line: &[u8]) {
+    let mut restored = Results {
+        measured: [Measured::EMPTY; MAX_CANDIDATES],
+        count: 0,
+        threads: 0,
+        elapsed: Duration::ZERO,
+    };
+    for word in line.split(|b| b.is_ascii_whitespace()) {
+        if let Some(threads) = word.strip_prefix(b"threads=").and_then(parse_u64) {
+            restored.threads = threads as usize;
+        } else if let Some(elapsed) = word.strip_prefix(b"elapsed_ns=").and_then(parse_u64) {
+            restored.elapsed = Duration::from_nanos(elapsed);
+        } else if let Some(value) = word.strip_prefix(b"timing=") {
+            match parse_timing(value) {
+                Some(measured) if restored.count < MAX_CANDIDATES => {
+                    restored.measured[restored.count] = measured;
+                    restored.count += 1;
+                }
+                // Partial measurements would misreport the calibration, so report none
+                _ => return,
+            }
+        }
+    }
+    if restored.count > 0 && restored.threads > 0 {
+        *results() = Some(restored);
+    }
+}
+
+// ========== Workloads ==========
+
+fn small_layout(index: usize) -> Layout {
+    // 16 << 0..=5 is 16 to 512 bytes, always a valid layout
+    unsafe { Layout::from_size_align_unchecked(16 << (index % 6), 8) }
+}
+
+fn large_layout(index: usize) -> Layout {
+    unsafe { Layout::from_size_align_unchecked((64 * 1024) << (index % 5), 16) }
+}
+
+/// Runs `ops` small allocate/free pairs with a sliding window of live blocks, returning
+/// `false` if the backend ran out of memory
+unsafe fn small_workload(allocator_id: u8, ops: usize) -> bool {
+    let mut live = [core::ptr::null_mut::<u8>(); SMALL_WINDOW];
+    let mut ok = true;
+    for op in 0..ops {
+        let slot = op % SMALL_WINDOW;
+        if !live[slot].is_null() {
+            // The block in this slot was allocated SMALL_WINDOW operations ago
+            RuntimeAllocator::dealloc_with(allocator_id, live[slot], small_layout(op - SMALL_WINDOW));
+        }
+        // black_box keeps the compiler from pairing up and eliding malloc/free
+        live[slot] = black_box(RuntimeAllocator::alloc_with(allocator_id, small_layout(op)));
+        if live[slot].is_null() {
+            ok = false;
+        } else {
+            live[slot].write(op as u8);
+        }
+    }
+    for op in ops.saturating_sub(SMALL_WINDOW)..ops {
+        let ptr = live[op % SMALL_WINDOW];
+        if !ptr.is_null() {
+            RuntimeAllocator::dealloc_with(allocator_id, ptr, small_layout(op));
+        }
+    }
+    ok
+}
+
+unsafe fn large_workload(allocator_id: u8) -> bool {
+    for op in 0..LARGE_OPS {
+        let layout = large_layout(op);
+        let ptr = black_box(RuntimeAllocator::alloc_with(allocator_id, layout));
+        if ptr.is_null() {
+            return false;
+        }
+        ptr.write(op as u8);
+        RuntimeAllocator::dealloc_with(allocator_id, ptr, layout);
+    }
+    true
+}
+
+/// Mean nanoseconds per operation, or `u64::MAX` when the workload failed
+fn per_op(started: Instant, ops: usize, ok: bool) -> u64 {
+    if ok {
+        (started.elapsed().as_nanos() / ops as u128) as u64
+    } else {
+        u64::MAX
+    }
+}
+
+// Threads are started with pthreads directly: std::thread would allocate through the global
+// allocator, which is still choosing its backend
+
+static THREAD_FAILURES: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn small_thread(arg: *mut libc::c_void) -> *mut libc::c_void {
+    if !unsafe { small_workload(arg as usize as u8, THREAD_OPS) } {
+        THREAD_FAILURES.fetch_add(1, Ordering::Relaxed);
+    }
+    core::ptr::null_mut()
+}
+
+/// Starts `count` threads running `f(arg)` and waits for them, returning `false` if any
+/// could not be started
+unsafe fn run_threads(
+    count: usize,
+    f: extern "C" fn(*mut libc::c_void) -> *mut libc::c_void,
+    arg: usize,
+) -> bool {
+    let mut handles: [libc::pthread_t; MAX_THREADS] = core::mem::zeroed();
+    let mut started = 0;
+    while started < count {
+        if libc::pthread_create(&mut handles[started], core::ptr::null(), f, arg as *mut libc::c_void) != 0 {
+            break;
+        }
+        started += 1;
+    }
+    for handle in &handles[..started] {
+        libc::pthread_join(*handle, core::ptr::null_mut());
+    }
+    started == count
+}
+
+/// Blocks in flight from the allocating thread to the freeing thread
+struct Handoff {
+    blocks: [AtomicUsize; CROSS_BATCH],
+    full: AtomicBool,
+    allocator_id: AtomicU8,
+}
+
+static HANDOFF: Handoff = Handoff {
+    blocks: [const { AtomicUsize::new(0) }; CROSS_BATCH],
+    full: AtomicBool::new(false),
+    allocator_id: AtomicU8::new(0),
+};
+
+fn cross_layout() -> Layout {
+    unsafe { Layout::from_size_align_unchecked(64, 8) }
+}
+
+fn wait_until_full(full: bool) {
+    while HANDOFF.full.load(Ordering::Acquire) != full {
+        unsafe { libc::sched_yield() };
+    }
+}
+
+extern "C" fn cross_thread_freer(_: *mut libc::c_void) -> *mut libc::c_void {
+    let allocator_id = HANDOFF.allocator_id.load(Ordering::Relaxed);
+    for _ in 0..CROSS_ROUNDS {
+        wait_until_full(true);
+        for block in &HANDOFF.blocks {
+            let ptr = block.swap(0, Ordering::Relaxed) as *mut u8;
+            if !ptr.is_null() {
+                unsafe { RuntimeAllocator::dealloc_with(allocator_id, ptr, cross_layout()) };
+            }
+        }
+        HANDOFF.full.store(false, Ordering::Release);
+    }
+    core::ptr::null_mut()
+}
+
+/// Allocates batches on this thread that a second thread frees
+unsafe fn cross_thread_workload(allocator_id: u8) -> bool {
+    HANDOFF.allocator_id.store(allocator_id, Ordering::Relaxed);
+    HANDOFF.full.store(false, Ordering::Relaxed);
+
+    let mut freer: libc::pthread_t = core::mem::zeroed();
+    if libc::pthread_create(&mut freer, core::ptr::null(), cross_thread_freer, core::ptr::null_mut()) != 0 {
+        return false;
+    }
+    let mut ok = true;
+    for _ in 0..CROSS_ROUNDS {
+        wait_until_full(false);
+        for block in &HANDOFF.blocks {
+            let ptr = black_box(RuntimeAllocator::alloc_with(allocator_id, cross_layout()));
+            ok &= !ptr.is_null();
+            block.store(ptr as usize, Ordering::Relaxed);
+        }
+        HANDOFF.full.store(true, Ordering::Release);
+    }
+    libc::pthread_join(freer, core::ptr::null_mut());
+    ok
+}
+
+unsafe fn measure(allocator_id: u8, threads: usize) -> Option<Measured> {
+    // Untimed warm-up, so a backend that has not served the process yet is not charged for
+    // mapping its first memory
+    small_workload(allocator_id, SMALL_WINDOW * 4);
+    large_workload(allocator_id);
+
+    let started = Instant::now();
+    let ok = small_workload(allocator_id, SMALL_OPS);
+    let small_ns = per_op(started, SMALL_OPS, ok);
+
+    let started = Instant::now();
+    let ok = large_workload(allocator_id);
+    let large_ns = per_op(started, LARGE_OPS, ok);
+
+    THREAD_FAILURES.store(0, Ordering::Relaxed);
+    let started = Instant::now();
+    if !run_threads(threads, small_thread, allocator_id as usize) {
+        return None;
+    }
+    let ok = THREAD_FAILURES.load(Ordering::Relaxed) == 0;
+    let multi_thread_ns = per_op(started, threads * THREAD_OPS, ok);
+
+    let started = Instant::now();
+    let ok = cross_thread_workload(allocator_id);
+    let cross_thread_ns = per_op(started, CROSS_ROUNDS * CROSS_BATCH, ok);
+
+    Some(Measured { allocator_id, small_ns, large_ns, multi_thread_ns, cross_thread_ns })
+}
+
+/// Allocator IDs worth measuring on this build and platform under the active security profile
+fn candidates() -> ([u8; MAX_CANDIDATES], usize) {
+    let mut ids = [1, 0];
+    let mut count = 1;
+    if let Some(mimalloc) = selectable_mimalloc() {
+        ids[count] = mimalloc;
+        count += 1;
+    }
+    (ids, count)
+}
+
+/// Calibrates every usable backend and returns the fastest, or `None` when there is nothing
+/// to choose from or threads could not be started, leaving the decision to the core count
+///
+/// Called from the first allocation on platforms without a compile-time choice. Allocations
+/// racing with it on other threads wait for the decision, since blocks must never cross
+/// backends.
+pub(crate) fn select() -> Option<u8> {
+    let (ids, count) = candidates();
+    if count < 2 {
+        return None;
+    }
+    if CALIBRATING.swap(true, Ordering::AcqRel) {
+        loop {
+            let selected = RUNTIME_ALLOCATOR_ID.load(Ordering::Acquire);
+            if selected != 0 {
+                return Some(selected);
+            }
+            unsafe { libc::sched_yield() };
+        }
+    }
+
+    let started = Instant::now();
+    let threads = get_cpu_cores_safe().clamp(2, MAX_THREADS);
+    let mut measured = [Measured::EMPTY; MAX_CANDIDATES];
+    for (slot, &allocator_id) in measured.iter_mut().zip(&ids[..count]) {
+        *slot = unsafe { measure(allocator_id, threads)? };
+    }
+
+    let best = measured[..count]
+        .iter()
+        .min_by_key(|candidate| candidate.timing().total_ns())?
+        .allocator_id;
+
+    // Give back what a losing mimalloc cached during calibration
+    #[cfg(all(
+        any(feature = "_mimalloc", feature = "_mimalloc_secure"),
+        not(target_arch = "wasm32"),
+        not(debug_assertions)
+    ))]
+    if !matches!(best, 2 | 5) {
+        unsafe { libmimalloc_sys::mi_collect(true) };
+    }
+
+    *results() = Some(Results { measured, count, threads, elapsed: started.elapsed() });
+    Some(best)
+}
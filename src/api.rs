@@ -2,11 +2,22 @@ use core::sync::atomic::Ordering;
 #[cfg(not(target_os = "none"))] use once_cell::sync::Lazy;
 use crate::logging::smart_try_flush_log;
 use crate::types::{AllocatorInfo, AllocatorType, SystemInfo};
+#[cfg(not(target_os = "none"))]
+use crate::types::LibcFlavor;
 use crate::platform::{RUNTIME_ALLOCATOR_ID};
-use crate::platform::is_embedded_target;
+#[cfg(not(target_os = "none"))]
+use crate::platform::{
+    decide, fixed_allocator, get_effective_cpu_cores_safe, selectable_mimalloc, usable_mimalloc, SelectionInputs,
+};
+use crate::profile::{active_profile, security_features};
+#[cfg(not(target_os = "none"))]
+use crate::reason::{describe, recorded, SelectionReason};
 use crate::runtime::RuntimeAllocator;
 use crate::system::collect_system_info;
-use crate::format::format_memory_size;
+#[cfg(not(target_os = "none"))]
+use crate::workload::workload_hint;
+#[cfg(not(target_os = "none"))]
+use crate::lowmem::classify;
 #[cfg(not(target_os = "none"))]
 static ALLOCATOR_INFO: Lazy<AllocatorInfo> = Lazy::new(|| {
     let system_info = collect_system_info();
@@ -19,50 +30,37 @@ static ALLOCATOR_INFO: Lazy<AllocatorInfo> = Lazy::new(|| {
         allocator_id
     };
 
-    let (_, mut reason) = get_allocator_selection_result(&system_info);
+    // A forced backend is stored before `main` without logging, so its choice is logged here
+    RuntimeAllocator::log_allocator_selection(final_allocator_id);
+
+    #[cfg(all(feature = "adaptive", unix))]
+    let calibration = crate::adaptive::calibration();
+    #[cfg(not(all(feature = "adaptive", unix)))]
+    let calibration = None;
+
+    let workload = crate::workload::applied_hint();
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    let glibc_tuning = crate::glibc::applied();
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    let glibc_tuning = None;
 
     // Determine type based on actually selected allocator ID (may differ due to feature disable)
-    let allocator_type = match final_allocator_id {
-        5 => AllocatorType::MimallocSecure,
-        2 => AllocatorType::Mimalloc,
-        4 => AllocatorType::EmbeddedHeap,
-        _ => AllocatorType::System,
-    };
+    let allocator_type = AllocatorType::from_id(final_allocator_id);
 
-    // Add "selected by runtime analysis" prefix to actual allocator info, extract hardware info part
-    let hardware_info = if reason.contains('(') && reason.contains(')') {
-        reason
-            .split_once('(')
-            .and_then(|(_prefix, suffix)| suffix.split_once(')').map(|(info, _)| info))
-            .unwrap_or("")
-    } else {
-        ""
-    };
+    let reason = describe(recorded(), final_allocator_id, &system_info);
 
-    reason = match final_allocator_id {
-        5 => format!(
-            "mimalloc-secure selected by runtime hardware analysis ({})",
-            hardware_info
-        ),
-        2 => format!(
-            "mimalloc selected by runtime hardware analysis ({})",
-            hardware_info
-        ),
-        4 => {
-            // For embedded allocator, preserve the original compile-time selection info
-            reason
-        },
-        _ => {
-            // For system allocator, preserve the original detailed reason as-is
-            // (already includes correct "compile-time selected" or platform-specific info)
-            reason
-        },
-    };
+    let security_profile = active_profile();
 
     AllocatorInfo {
         allocator_type,
         reason,
         system_info,
+        security_profile,
+        security_features: security_features(security_profile, final_allocator_id),
+        calibration,
+        workload,
+        glibc_tuning,
     }
 });
 
@@ -90,6 +88,8 @@ fn ensure_allocator_info_ready() {
                 allocator_type: AllocatorType::EmbeddedHeap,
                 reason: "embedded-alloc selected for no_std environment",
                 system_info,
+                security_profile: active_profile(),
+                security_features: security_features(active_profile(), 4),
             });
         }
     }
@@ -170,83 +170,35 @@ pub fn get_allocator_type() -> AllocatorType {
 /// Get allocator selection result and reason (internal function)
 #[cfg(not(target_os = "none"))]
 fn get_allocator_selection_result(system_info: &SystemInfo) -> (AllocatorType, String) {
-    let total_mem = format_memory_size(system_info.total_memory_bytes);
+    let (allocator_id, reason) = recommend_allocator(system_info);
+    (AllocatorType::from_id(allocator_id), describe(reason, allocator_id, system_info))
+}
 
-    if system_info.is_wasm {
-        (
-            AllocatorType::System,
-            format!("system allocator - WASM environment ({} total RAM)", total_mem),
-        )
-    } else if system_info.is_debug {
-        (
-            AllocatorType::System,
-            format!(
-                "system allocator - debug build ({} cores, {} total RAM)",
-                system_info.cpu_cores, total_mem
-            ),
-        )
-    } else if is_embedded_target() {
-        (
-            AllocatorType::EmbeddedHeap,
-            format!("embedded-alloc allocator - embedded environment ({} total RAM)", total_mem),
-        )
-    } else if system_info.os_type == "android" {
-        (
-            AllocatorType::System,
-            format!(
-                "Android platform - Scudo allocator (security-first, use-after-free protection) ({} cores, {} total RAM)",
-                system_info.cpu_cores, total_mem
-            ),
-        )
-    } else if system_info.os_type == "ios" {
-        (
-            AllocatorType::System,
-            format!(
-                "iOS platform - libmalloc allocator (Apple-optimized, memory pressure handling) ({} cores, {} total RAM)",
-                system_info.cpu_cores, total_mem
-            ),
-        )
-    } else if system_info.os_type == "freebsd" || system_info.os_type == "netbsd" {
-        (
-            AllocatorType::System,
-            format!(
-                "BSD platform - native jemalloc (highly optimized, deep system integration) ({} cores, {} total RAM)",
-                system_info.cpu_cores, total_mem
-            ),
-        )
-    } else if system_info.os_type == "openbsd" {
-        (
-            AllocatorType::System,
-            format!(
-                "OpenBSD platform - security-hardened allocator (exploit mitigation, aggressive hardening) ({} cores, {} total RAM)",
-                system_info.cpu_cores, total_mem
-            ),
-        )
-    } else if system_info.os_type == "solaris" || system_info.os_type == "illumos" {
-        (
-            AllocatorType::System,
-            format!(
-                "Solaris platform - libumem allocator (NUMA-aware, enterprise-grade performance) ({} cores, {} total RAM)",
-                system_info.cpu_cores, total_mem
-            ),
-        )
-    } else if system_info.cpu_cores >= 2 {
-        (
-            AllocatorType::Mimalloc,
-            format!(
-                "mimalloc allocator - high-performance multi-threaded environment ({} cores, {} total RAM)",
-                system_info.cpu_cores, total_mem
-            ),
-        )
-    } else {
-        (
-            AllocatorType::System,
-            format!(
-                "system allocator - low-performance environment ({} cores, {} total RAM)",
-                system_info.cpu_cores, total_mem
-            ),
-        )
+/// Re-applies the selection rules to freshly collected system information
+#[cfg(not(target_os = "none"))]
+fn recommend_allocator(system_info: &SystemInfo) -> (u8, SelectionReason) {
+    if let Some(fixed) = fixed_allocator(system_info.debugging_tool.is_some()) {
+        return fixed;
     }
+
+    let inputs = SelectionInputs {
+        memory_class: classify(system_info.effective_memory_bytes),
+        memory: system_info.effective_memory_bytes,
+        cpu_cores: get_effective_cpu_cores_safe(),
+        numa_nodes: system_info.numa_node_count,
+        musl: system_info.libc_flavor == LibcFlavor::Musl,
+        workload: workload_hint(),
+        usable_mimalloc: usable_mimalloc(),
+        selectable_mimalloc: selectable_mimalloc(),
+    };
+
+    // Calibrating again would disturb the running allocator, so its earlier winner stands in
+    #[cfg(all(feature = "adaptive", unix))]
+    let calibrate = || crate::adaptive::is_calibrated().then(|| RUNTIME_ALLOCATOR_ID.load(Ordering::Acquire));
+    #[cfg(not(all(feature = "adaptive", unix)))]
+    let calibrate = || None;
+
+    decide(&inputs, calibrate)
 }
 
 /// Simplified allocator selection for no_std environments
@@ -292,6 +244,9 @@ fn get_allocator_selection_result(_system_info: &SystemInfo) -> (AllocatorType,
 /// }
 /// ```
 ///
+/// With the `selection-cache` feature, the reason also notes when the persisted decision is
+/// stale, i.e. was made on different hardware, kernel or binary.
+///
 /// # Performance Notes
 ///
 /// This function re-performs system hardware detection, with slightly higher overhead than [`get_allocator_info()`].
@@ -299,7 +254,19 @@ fn get_allocator_selection_result(_system_info: &SystemInfo) -> (AllocatorType,
 pub fn get_recommended_allocator() -> (AllocatorType, String) {
     smart_try_flush_log();
     let system_info = collect_system_info();
-    get_allocator_selection_result(&system_info)
+    let (recommended, reason) = get_allocator_selection_result(&system_info);
+
+    #[cfg(all(feature = "selection-cache", unix))]
+    let reason = match crate::cache::selection_cache_status() {
+        crate::cache::SelectionCacheStatus {
+            state: crate::cache::CacheState::Stale,
+            cached_allocator: Some(cached),
+            ..
+        } => format!("{}; cached decision ({:?}) is stale and will be redone on next start", reason, cached),
+        _ => reason,
+    };
+
+    (recommended, reason)
 }
 
 #[cfg(target_os = "none")]
@@ -0,0 +1,91 @@
+use core::alloc::{GlobalAlloc, Layout};
+use crate::platform::{backend_name, can_use_debug_guard, can_use_mimalloc, can_use_mimalloc_secure};
+use crate::runtime::RuntimeAllocator;
+use crate::types::AllocatorType;
+// ========== Direct Backend Access ==========
+
+/// One allocator backend used directly, independent of the global selection
+///
+/// Implements [`GlobalAlloc`] without any of the crate's instrumentation, so each backend
+/// compiled into the build can be measured or used side by side with the others, whichever
+/// one the global allocator selected. Blocks must be freed by the same backend that
+/// allocated them.
+///
+/// mimalloc is compiled either as the regular or as the secure build, never both: with the
+/// `secure` feature only [`AllocatorType::MimallocSecure`] is available.
+///
+/// # Example
+///
+/// ```rust
+/// use auto_allocator::BackendAllocator;
+/// use std::alloc::{GlobalAlloc, Layout};
+///
+/// for backend in BackendAllocator::available() {
+///     let layout = Layout::from_size_align(64, 8).unwrap();
+///     unsafe {
+///         let ptr = backend.alloc(layout);
+///         assert!(!ptr.is_null());
+///         backend.dealloc(ptr, layout);
+///     }
+///     println!("{} works", backend.name());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackendAllocator {
+    allocator_id: u8,
+}
+
+impl BackendAllocator {
+    /// Returns the backend of the given type, or `None` if it is not compiled into this build
+    /// or not usable on this platform
+    pub fn new(allocator_type: AllocatorType) -> Option<Self> {
+        let allocator_id = match allocator_type {
+            AllocatorType::System => 1,
+            AllocatorType::Mimalloc if can_use_mimalloc() && !can_use_mimalloc_secure() => 2,
+            AllocatorType::MimallocSecure if can_use_mimalloc_secure() => 5,
+            AllocatorType::DebugGuard if can_use_debug_guard() => 6,
+            _ => return None,
+        };
+        Some(BackendAllocator { allocator_id })
+    }
+
+    /// Returns every backend usable in this build, the system allocator first
+    pub fn available() -> Vec<Self> {
+        [
+            AllocatorType::System,
+            AllocatorType::Mimalloc,
+            AllocatorType::MimallocSecure,
+            AllocatorType::DebugGuard,
+        ]
+        .into_iter()
+        .filter_map(Self::new)
+        .collect()
+    }
+
+    /// Type of this backend
+    pub fn allocator_type(&self) -> AllocatorType {
+        AllocatorType::from_id(self.allocator_id)
+    }
+
+    /// Name of this backend as accepted by `AUTO_ALLOCATOR_BACKEND`
+    pub fn name(&self) -> &'static str {
+        backend_name(self.allocator_id)
+    }
+
+    #[cfg(all(feature = "trace", unix))]
+    pub(crate) fn allocator_id(&self) -> u8 {
+        self.allocator_id
+    }
+}
+
+unsafe impl GlobalAlloc for BackendAllocator {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        RuntimeAllocator::alloc_with(self.allocator_id, layout)
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        RuntimeAllocator::dealloc_with(self.allocator_id, ptr, layout)
+    }
+}
@@ -0,0 +1,198 @@
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, Ordering};
+use crate::reentrancy::ReentrancyGuard;
+use crate::system::get_effective_memory_limit;
+// ========== Memory Budget Enforcement ==========
+
+/// Maximum number of soft-limit callbacks that can be registered
+const MAX_CALLBACKS: usize = 8;
+
+/// After firing, soft-limit callbacks re-arm once usage falls below this share of the soft limit
+const REARM_PERCENT: u64 = 90;
+
+/// Process heap budget with a soft and a hard limit
+///
+/// - **Soft limit**: crossing it fires the callbacks registered with [`on_soft_limit()`], so the
+///   application can shed caches before memory runs out
+/// - **Hard limit**: allocations that would exceed it fail cleanly (null from the allocator)
+///   instead of letting the kernel OOM killer take down the whole process
+///
+/// # Example
+///
+/// ```rust
+/// use auto_allocator::MemoryBudget;
+///
+/// // 75% / 90% of the cgroup limit, or of physical memory outside containers
+/// let budget = MemoryBudget::from_system();
+/// assert!(budget.soft_limit_bytes < budget.hard_limit_bytes);
+///
+/// auto_allocator::set_memory_budget(budget);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBudget {
+    /// Live heap bytes above which soft-limit callbacks fire
+    pub soft_limit_bytes: u64,
+
+    /// Live heap bytes above which allocations fail
+    pub hard_limit_bytes: u64,
+}
+
+impl MemoryBudget {
+    /// Creates a budget with explicit soft and hard limits in bytes
+    pub const fn new(soft_limit_bytes: u64, hard_limit_bytes: u64) -> Self {
+        Self { soft_limit_bytes, hard_limit_bytes }
+    }
+
+    /// Derives a budget from the memory available to this process
+    ///
+    /// Uses the cgroup memory limit when running in a container, otherwise total physical
+    /// memory ([`SystemInfo::total_memory_bytes`](crate::SystemInfo::total_memory_bytes)).
+    /// The soft limit is 75% and the hard limit 90% of that amount, leaving headroom for
+    /// memory that is not allocated through the Rust heap.
+    pub fn from_system() -> Self {
+        let available = get_effective_memory_limit();
+        Self::new(available / 100 * 75, available / 100 * 90)
+    }
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        Self::from_system()
+    }
+}
+
+/// Snapshot of heap usage against the configured budget
+///
+/// Returned by [`memory_budget_status()`] and passed to soft-limit callbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetStatus {
+    /// Bytes currently allocated through the global allocator
+    pub live_bytes: u64,
+
+    /// Highest value of `live_bytes` seen so far
+    pub peak_bytes: u64,
+
+    /// Configured soft limit (`u64::MAX` when no budget is set)
+    pub soft_limit_bytes: u64,
+
+    /// Configured hard limit (`u64::MAX` when no budget is set)
+    pub hard_limit_bytes: u64,
+
+    /// Whether usage is currently above the soft limit
+    pub soft_limit_exceeded: bool,
+
+    /// Number of allocations refused because of the hard limit
+    pub rejected_allocations: u64,
+}
+
+static LIVE_BYTES: AtomicU64 = AtomicU64::new(0);
+static PEAK_BYTES: AtomicU64 = AtomicU64::new(0);
+static SOFT_LIMIT: AtomicU64 = AtomicU64::new(u64::MAX);
+static HARD_LIMIT: AtomicU64 = AtomicU64::new(u64::MAX);
+static SOFT_EXCEEDED: AtomicBool = AtomicBool::new(false);
+static REJECTED: AtomicU64 = AtomicU64::new(0);
+
+static CALLBACKS: [AtomicPtr<()>; MAX_CALLBACKS] =
+    [const { AtomicPtr::new(core::ptr::null_mut()) }; MAX_CALLBACKS];
+
+/// Installs a process heap budget
+///
+/// Usage is tracked from process start, so the budget also covers allocations made before it
+/// was installed. A budget below current usage makes every new allocation fail.
+pub fn set_memory_budget(budget: MemoryBudget) {
+    SOFT_LIMIT.store(budget.soft_limit_bytes, Ordering::Relaxed);
+    HARD_LIMIT.store(budget.hard_limit_bytes, Ordering::Relaxed);
+    SOFT_EXCEEDED.store(false, Ordering::Relaxed);
+}
+
+/// Removes the process heap budget; usage keeps being tracked
+pub fn clear_memory_budget() {
+    set_memory_budget(MemoryBudget::new(u64::MAX, u64::MAX));
+}
+
+/// Registers a callback fired when heap usage crosses the soft limit
+///
+/// Callbacks run synchronously on the thread whose allocation crossed the limit, from inside
+/// the global allocator. They may allocate and free, but must not block on locks the
+/// allocating code could be holding; setting a flag or waking a cleanup thread is the
+/// typical pattern. They fire once per crossing and re-arm after usage falls back below 90%
+/// of the soft limit.
+///
+/// Returns `false` if all callback slots are taken.
+pub fn on_soft_limit(callback: fn(&BudgetStatus)) -> bool {
+    let raw = callback as *mut ();
+    CALLBACKS.iter().any(|slot| {
+        slot.compare_exchange(core::ptr::null_mut(), raw, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+    })
+}
+
+/// Returns current heap usage against the configured budget
+pub fn memory_budget_status() -> BudgetStatus {
+    BudgetStatus {
+        live_bytes: LIVE_BYTES.load(Ordering::Relaxed),
+        peak_bytes: PEAK_BYTES.load(Ordering::Relaxed),
+        soft_limit_bytes: SOFT_LIMIT.load(Ordering::Relaxed),
+        hard_limit_bytes: HARD_LIMIT.load(Ordering::Relaxed),
+        soft_limit_exceeded: SOFT_EXCEEDED.load(Ordering::Relaxed),
+        rejected_allocations: REJECTED.load(Ordering::Relaxed),
+    }
+}
+
+/// Reserves budget for an allocation, called from `RuntimeAllocator::alloc`
+///
+/// Returns `false` if the allocation would exceed the hard limit.
+#[inline]
+pub(crate) fn reserve(size: usize) -> bool {
+    let size = size as u64;
+    let live = LIVE_BYTES.fetch_add(size, Ordering::Relaxed) + size;
+
+    if live > HARD_LIMIT.load(Ordering::Relaxed) {
+        LIVE_BYTES.fetch_sub(size, Ordering::Relaxed);
+        REJECTED.fetch_add(1, Ordering::Relaxed);
+        return false;
+    }
+
+    PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+    if live > SOFT_LIMIT.load(Ordering::Relaxed) && !SOFT_EXCEEDED.load(Ordering::Relaxed) {
+        soft_limit_crossed();
+    }
+    true
+}
+
+/// Returns budget for freed (or failed) allocations, called from `RuntimeAllocator::dealloc`
+#[inline]
+pub(crate) fn release(size: usize) {
+    let live = LIVE_BYTES.fetch_sub(size as u64, Ordering::Relaxed) - size as u64;
+
+    if SOFT_EXCEEDED.load(Ordering::Relaxed) {
+        let rearm_below = SOFT_LIMIT.load(Ordering::Relaxed) / 100 * REARM_PERCENT;
+        if live < rearm_below {
+            SOFT_EXCEEDED.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cold]
+fn soft_limit_crossed() {
+    // Callbacks that allocate must not re-enter this path on the same thread. The guard is taken
+    // before the flag, so a crossing inside another hook is left for the next allocation
+    // instead of being consumed without running the callbacks.
+    let Some(_guard) = ReentrancyGuard::enter() else {
+        return;
+    };
+    if SOFT_EXCEEDED
+        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+    {
+        return;
+    }
+
+    let status = memory_budget_status();
+    for slot in &CALLBACKS {
+        let raw = slot.load(Ordering::Acquire);
+        if !raw.is_null() {
+            let callback: fn(&BudgetStatus) = unsafe { core::mem::transmute(raw) };
+            let _ = std::panic::catch_unwind(|| callback(&status));
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use core::ffi::CStr;
+use core::fmt::Write as _;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::path::PathBuf;
+use crate::platform::{can_use_mimalloc, can_use_mimalloc_secure, get_cpu_cores_safe, with_env_var};
+use crate::rawlog::{write_fd, StackBuffer};
+use crate::system::{get_numa_node_count_safe, get_total_memory_safe, read_file_into};
+use crate::types::AllocatorType;
+// ========== Persisted Selection Cache ==========
+
+/// First word of every cache file, bumped whenever the format changes
+const FORMAT: &[u8] = b"auto-allocator-selection-v2";
+
+/// Longest cache path, including the file name
+const MAX_PATH: usize = 512;
+
+/// Longest cache entry, room for the decision and the calibration behind it
+const MAX_ENTRY: usize = 512;
+
+type PathBuffer = StackBuffer<MAX_PATH>;
+
+/// What the selection cache file for this binary on this machine holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheState {
+    /// A decision made under the current fingerprint, reused instead of selecting again
+    Valid,
+
+    /// A decision made under another fingerprint: the hardware, kernel or binary changed since
+    Stale,
+
+    /// No decision has been cached yet
+    Missing,
+
+    /// The file exists but is not a cache file this version understands
+    Corrupt,
+
+    /// Caching is off (`AUTO_ALLOCATOR_CACHE=off`) or no cache directory could be determined
+    Disabled,
+}
+
+/// Snapshot of the persisted selection cache, returned by [`selection_cache_status()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionCacheStatus {
+    /// What the cache file currently holds
+    pub state: CacheState,
+
+    /// Location of the cache file, `None` when no cache directory could be determined
+    pub path: Option<PathBuf>,
+
+    /// Fingerprint of the current hardware, kernel and binary
+    pub fingerprint: u64,
+
+    /// Allocator recorded in the file, for valid and stale entries
+    pub cached_allocator: Option<AllocatorType>,
+
+    /// Whether this process took its allocator from the cache instead of selecting it
+    pub used_at_startup: bool,
+}
+
+static USED_AT_STARTUP: AtomicBool = AtomicBool::new(false);
+static FINGERPRINT: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    ReadWrite,
+    ReadOnly,
+    Off,
+}
+
+/// Reads `AUTO_ALLOCATOR_CACHE` (`read-write` by default, `read-only` or `off`)
+fn mode() -> Mode {
+    with_env_var(c"AUTO_ALLOCATOR_CACHE", |value| match value {
+        b"off" => Mode::Off,
+        b"read-only" => Mode::ReadOnly,
+        _ => Mode::ReadWrite,
+    })
+    .unwrap_or(Mode::ReadWrite)
+}
+
+// ========== Fingerprint ==========
+
+/// 64-bit FNV-1a, small enough to run inside the allocator
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Self {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+        // Separator, so adjacent fields cannot run into each other
+        self.0 = (self.0 ^ 0xff).wrapping_mul(0x0100_0000_01b3);
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+}
+
+fn c_chars(chars: &[libc::c_char]) -> &[u8] {
+    let len = chars.iter().position(|&c| c == 0).unwrap_or(chars.len());
+    // c_char is i8 or u8 depending on the target; both have the layout of u8
+    unsafe { core::slice::from_raw_parts(chars.as_ptr() as *const u8, len) }
+}
+
+/// Hashes everything a cached decision depends on: crate version and usable backends,
+/// hardware, kernel, and (on Linux) the identity of the executable
+pub(crate) fn fingerprint() -> u64 {
+    let cached = FINGERPRINT.load(Ordering::Relaxed);
+    if cached != 0 {
+        return cached;
+    }
+
+    let mut hash = Fnv::new();
+    hash.write(env!("CARGO_PKG_VERSION").as_bytes());
+    hash.write_u64(
+        can_use_mimalloc() as u64
+            | (can_use_mimalloc_secure() as u64) << 1
+            | (cfg!(feature = "adaptive") as u64) << 2,
+    );
+    // The security profile decides which mimalloc build may be selected
+    hash.write_u64(crate::profile::active_profile() as u64);
+    // A different workload hint may lead to a different decision
+    hash.write(crate::workload::workload_hint().map_or("", crate::workload::workload_name).as_bytes());
+
+    hash.write_u64(get_cpu_cores_safe() as u64);
+    hash.write_u64(get_total_memory_safe());
+    // Cgroup limits and thresholds move hosts between memory classes
+    hash.write_u64(crate::system::get_effective_memory_limit());
+    let (low_memory, constrained_memory) = crate::lowmem::thresholds();
+    hash.write_u64(low_memory);
+    hash.write_u64(constrained_memory);
+    hash.write_u64(get_numa_node_count_safe() as u64);
+
+    unsafe {
+        let mut uname: libc::utsname = core::mem::zeroed();
+        if libc::uname(&mut uname) == 0 {
+            hash.write(c_chars(&uname.sysname));
+            hash.write(c_chars(&uname.release));
+            hash.write(c_chars(&uname.version));
+            hash.write(c_chars(&uname.machine));
+        }
+    }
+
+    // A rebuilt or replaced binary may behave differently, so it invalidates the decision
+    #[cfg(target_os = "linux")]
+    unsafe {
+        let mut exe: libc::stat = core::mem::zeroed();
+        if libc::stat(c"/proc/self/exe".as_ptr(), &mut exe) == 0 {
+            hash.write_u64(exe.st_dev);
+            hash.write_u64(exe.st_ino);
+            hash.write_u64(exe.st_size as u64);
+            hash.write_u64(exe.st_mtime as u64);
+            hash.write_u64(exe.st_mtime_nsec as u64);
+        }
+    }
+
+    let fingerprint = hash.0.max(1);
+    FINGERPRINT.store(fingerprint, Ordering::Relaxed);
+    fingerprint
+}
+
+// ========== Cache File ==========
+
+fn push_env(path: &mut PathBuffer, name: &CStr) -> bool {
+    with_env_var(name, |value| match core::str::from_utf8(value) {
+        Ok(value) if !value.is_empty() => path.write_str(value).is_ok(),
+        _ => false,
+    })
+    .unwrap_or(false)
+}
+
+/// Builds the cache file path, NUL-terminated
+///
+/// The directory is `AUTO_ALLOCATOR_CACHE_DIR`, else `$XDG_CACHE_HOME/auto-allocator`, else
+/// `$HOME/.cache/auto-allocator`. Each executable gets its own file, named after a hash of its
+/// path, so different programs on one machine do not overwrite each other's decision.
+fn cache_path() -> Option<PathBuffer> {
+    let mut path = PathBuffer::new();
+    if !push_env(&mut path, c"AUTO_ALLOCATOR_CACHE_DIR") {
+        path = PathBuffer::new();
+        if push_env(&mut path, c"XDG_CACHE_HOME") {
+            path.write_str("/auto-allocator").ok()?;
+        } else {
+            path = PathBuffer::new();
+            if !push_env(&mut path, c"HOME") {
+                return None;
+            }
+            path.write_str("/.cache/auto-allocator").ok()?;
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let mut exe = [0u8; MAX_PATH];
+        let len = unsafe {
+            libc::readlink(c"/proc/self/exe".as_ptr(), exe.as_mut_ptr() as *mut libc::c_char, exe.len())
+        };
+        let mut hash = Fnv::new();
+        hash.write(&exe[..len.max(0) as usize]);
+        write!(path, "/selection-{:016x}", hash.0).ok()?;
+    }
+    #[cfg(not(target_os = "linux"))]
+    path.write_str("/selection").ok()?;
+
+    path.write_str("\0").ok()?;
+    // Truncated paths lose their terminator and are rejected here
+    CStr::from_bytes_with_nul(path.as_bytes()).ok()?;
+    Some(path)
+}
+
+fn as_cstr(path: &PathBuffer) -> &CStr {
+    // cache_path() only returns buffers holding a valid C string
+    unsafe { CStr::from_bytes_with_nul_unchecked(path.as_bytes()) }
+}
+
+fn field<'a>(line: &'a [u8], key: &[u8]) -> Option<&'a [u8]> {
+    line.split(|b| b.is_ascii_whitespace())
+        .find_map(|word| word.strip_prefix(key))
+}
+
+fn parse_hex(digits: &[u8]) -> Option<u64> {
+    let digits = core::str::from_utf8(digits).ok()?;
+    u64::from_str_radix(digits, 16).ok()
+}
+
+/// Reads a cache file: `auto-allocator-selection-v2 fingerprint=<hex> allocator=<id>`, followed
+/// by the calibration measurements when an `adaptive` calibration made the decision
+///
+/// `on_valid` sees the whole entry when its fingerprint matches `current`.
+fn read_entry(path: &CStr, current: u64, on_valid: impl FnOnce(&[u8])) -> Result<(u64, u8), CacheState> {
+    let mut buf = [0u8; MAX_ENTRY];
+    let len = read_file_into(path, &mut buf).ok_or(CacheState::Missing)?;
+    let line = &buf[..len];
+    if !line.starts_with(FORMAT) {
+        return Err(CacheState::Corrupt);
+    }
+
+    let fingerprint = field(line, b"fingerprint=").and_then(parse_hex);
+    let allocator_id = match field(line, b"allocator=") {
+        Some(b"1") => Some(1),
+        Some(b"2") if can_use_mimalloc() => Some(2),
+        Some(b"5") if can_use_mimalloc_secure() => Some(5),
+        _ => None,
+    };
+    match (fingerprint, allocator_id) {
+        (Some(fingerprint), Some(allocator_id)) => {
+            if fingerprint == current {
+                on_valid(line);
+            }
+            Ok((fingerprint, allocator_id))
+        }
+        _ => Err(CacheState::Corrupt),
+    }
+}
+
+/// Creates every missing directory on the way to the cache file
+fn create_parent_dirs(path: &[u8]) {
+    for (end, _) in path.iter().enumerate().skip(1).filter(|(_, &b)| b == b'/') {
+        let mut dir = [0u8; MAX_PATH];
+        dir[..end].copy_from_slice(&path[..end]);
+        unsafe { libc::mkdir(dir.as_ptr() as *const libc::c_char, 0o755) };
+    }
+}
+
+/// Writes the decision next to the cache file, then renames it into place so concurrent
+/// readers never see a partial entry
+fn write_entry(path: &PathBuffer, fingerprint: u64, allocator_id: u8) {
+    let target = path.as_bytes();
+    create_parent_dirs(target);
+
+    let mut temp = PathBuffer::new();
+    let name = core::str::from_utf8(&target[..target.len() - 1]).unwrap_or_default();
+    let _ = write!(temp, "{}.{}.tmp\0", name, unsafe { libc::getpid() });
+    let Ok(temp_path) = CStr::from_bytes_with_nul(temp.as_bytes()) else {
+        return;
+    };
+
+    let mut entry = StackBuffer::<MAX_ENTRY>::new();
+    let _ = write!(
+        entry,
+        "{} fingerprint={:016x} allocator={}",
+        core::str::from_utf8(FORMAT).unwrap_or_default(),
+        fingerprint,
+        allocator_id
+    );
+    // Saved with the decision, so runs that reuse it still report what it was based on
+    #[cfg(feature = "adaptive")]
+    let _ = crate::adaptive::write_record(&mut entry);
+    if entry.write_str("\n").is_err() {
+        // A truncated entry would not parse back, so keep the previous one instead
+        return;
+    }
+
+    unsafe {
+        let fd = libc::open(
+            temp_path.as_ptr(),
+            libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC | libc::O_CLOEXEC,
+            0o644 as libc::c_uint,
+        );
+        if fd < 0 {
+            return;
+        }
+        write_fd(fd, entry.as_bytes());
+        libc::close(fd);
+        if libc::rename(temp_path.as_ptr(), as_cstr(path).as_ptr()) != 0 {
+            libc::unlink(temp_path.as_ptr());
+        }
+    }
+}
+
+/// Returns the cached decision when it is valid, otherwise runs `select` and caches its result
+///
+/// Called from the first allocation, in place of the runtime part of the selection, so
+/// everything here works without allocating.
+pub(crate) fn cached_selection(select: fn() -> u8) -> u8 {
+    let mode = mode();
+    let path = match mode {
+        Mode::Off => None,
+        _ => cache_path(),
+    };
+    let Some(path) = path else {
+        return select();
+    };
+
+    let fingerprint = fingerprint();
+    #[cfg(feature = "adaptive")]
+    let restore = crate::adaptive::restore_record;
+    #[cfg(not(feature = "adaptive"))]
+    let restore = |_: &[u8]| {};
+    if let Ok((cached, allocator_id)) = read_entry(as_cstr(&path), fingerprint, restore) {
+        if cached == fingerprint {
+            // The fingerprint covers the hint and memory class, so they shaped the cached decision too
+            crate::lowmem::apply();
+            crate::workload::apply_hint();
+            USED_AT_STARTUP.store(true, Ordering::Relaxed);
+            crate::reason::record(crate::reason::SelectionReason::Cached);
+            return allocator_id;
+        }
+    }
+
+    let allocator_id = select();
+    if mode == Mode::ReadWrite {
+        write_entry(&path, fingerprint, allocator_id);
+    }
+    allocator_id
+}
+
+/// Whether this process took its allocator from the cache
+pub(crate) fn used_at_startup() -> bool {
+    USED_AT_STARTUP.load(Ordering::Relaxed)
+}
+
+// ========== Public API ==========
+
+/// Reads and validates the persisted selection cache
+///
+/// With the `selection-cache` feature, the result of runtime selection (the core-count rule or
+/// an `adaptive` calibration) is saved per executable and reused by later runs while the
+/// fingerprint of hardware, kernel and binary still matches. This reports what the cache
+/// holds right now without changing it. `AUTO_ALLOCATOR_CACHE=read-only` makes startup use a
+/// valid entry without ever writing one; `off` disables the cache.
+///
+/// # Example
+///
+/// ```rust
+/// use auto_allocator::{selection_cache_status, CacheState};
+///
+/// let status = selection_cache_status();
+/// if status.state == CacheState::Stale {
+///     println!("cached allocator decision is outdated and will be redone on next start");
+/// }
+/// ```
+pub fn selection_cache_status() -> SelectionCacheStatus {
+    let fingerprint = fingerprint();
+    let used_at_startup = used_at_startup();
+    let path = match mode() {
+        Mode::Off => None,
+        _ => cache_path(),
+    };
+    let Some(path) = path else {
+        return SelectionCacheStatus {
+            state: CacheState::Disabled,
+            path: None,
+            fingerprint,
+            cached_allocator: None,
+            used_at_startup,
+        };
+    };
+
+    let (state, cached_allocator) = match read_entry(as_cstr(&path), fingerprint, |_| {}) {
+        Ok((cached, allocator_id)) => {
+            let state = if cached == fingerprint { CacheState::Valid } else { CacheState::Stale };
+            (state, Some(AllocatorType::from_id(allocator_id)))
+        }
+        Err(state) => (state, None),
+    };
+    SelectionCacheStatus {
+        state,
+        path: Some(PathBuf::from(as_cstr(&path).to_string_lossy().into_owned())),
+        fingerprint,
+        cached_allocator,
+        used_at_startup,
+    }
+}
+
+/// Deletes the cached decision of this executable, so the next run selects from scratch
+///
+/// A missing cache file is not an error.
+pub fn invalidate_selection_cache() -> std::io::Result<()> {
+    let Some(path) = cache_path() else {
+        return Ok(());
+    };
+    match std::fs::remove_file(as_cstr(&path).to_string_lossy().as_ref()) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error),
+        _ => Ok(()),
+    }
+}
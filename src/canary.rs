@@ -0,0 +1,265 @@
+use core::alloc::Layout;
+use core::fmt::{self, Write as _};
+use std::sync::{Mutex, MutexGuard};
+use crate::rawlog::{write_backtrace, write_stderr, StackBuffer};
+use crate::runtime::RuntimeAllocator;
+// ========== Canary Redzones ==========
+
+/// Byte pattern filling the redzones on both sides of every allocation
+const CANARY: u8 = 0xAC;
+
+/// Minimum size of the redzone before each block: the list header plus at least 32 canary bytes
+const HEAD_REDZONE: usize = 64;
+
+/// Size of the redzone after each block
+const TAIL_REDZONE: usize = 16;
+
+/// Bookkeeping stored at the start of every over-allocated block
+///
+/// Live blocks form an intrusive doubly-linked list, so tracking them never allocates.
+#[repr(C)]
+struct Header {
+    prev: usize,
+    next: usize,
+    size: usize,
+    align: usize,
+}
+
+const HEADER_SIZE: usize = core::mem::size_of::<Header>();
+
+struct LiveBlocks {
+    first: usize,
+    blocks: usize,
+    bytes: usize,
+}
+
+static LIVE: Mutex<LiveBlocks> = Mutex::new(LiveBlocks { first: 0, blocks: 0, bytes: 0 });
+
+fn live() -> MutexGuard<'static, LiveBlocks> {
+    LIVE.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Side of a block whose redzone was overwritten
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanaryRegion {
+    /// Redzone before the block (buffer underflow)
+    Head,
+
+    /// Redzone after the block (buffer overflow)
+    Tail,
+}
+
+/// A smashed canary found by [`verify_heap()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapCorruption {
+    /// Address of the block as returned to the program
+    pub address: usize,
+
+    /// Size of the block's layout
+    pub size: usize,
+
+    /// Alignment of the block's layout
+    pub align: usize,
+
+    /// Which redzone was overwritten
+    pub region: CanaryRegion,
+
+    /// Position of the damaged byte counted outward from the block boundary, starting at 0
+    pub offset: usize,
+}
+
+impl fmt::Display for HeapCorruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (side, boundary) = match self.region {
+            CanaryRegion::Head => ("before", "start"),
+            CanaryRegion::Tail => ("after", "end"),
+        };
+        write!(
+            f,
+            "heap canary smashed {} block at {:#x} (size {}, align {}): byte {} from its {} overwritten",
+            side, self.address, self.size, self.align, self.offset, boundary
+        )
+    }
+}
+
+impl std::error::Error for HeapCorruption {}
+
+/// Live blocks checked by a successful [`verify_heap()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapCheck {
+    /// Number of live blocks whose redzones are intact
+    pub blocks: usize,
+
+    /// Total size of those blocks as requested by the program
+    pub bytes: usize,
+}
+
+/// Size of the redzone before a block, a multiple of its alignment
+fn head_redzone(align: usize) -> usize {
+    HEAD_REDZONE.max(align)
+}
+
+/// Checks the redzones of one block, returning the first damaged byte
+unsafe fn check_block(header: *const Header) -> Option<HeapCorruption> {
+    let (size, align) = ((*header).size, (*header).align);
+    let base = header as *const u8;
+    let user = base.add(head_redzone(align));
+    let corruption = |region, offset| HeapCorruption {
+        address: user as usize,
+        size,
+        align,
+        region,
+        offset,
+    };
+
+    // Scan outwards from the block, since small overruns hit the nearest bytes first
+    let head_canary = base.add(HEADER_SIZE);
+    let head_len = head_redzone(align) - HEADER_SIZE;
+    for offset in 0..head_len {
+        if *head_canary.add(head_len - 1 - offset) != CANARY {
+            return Some(corruption(CanaryRegion::Head, offset));
+        }
+    }
+    let tail = user.add(size);
+    for offset in 0..TAIL_REDZONE {
+        if *tail.add(offset) != CANARY {
+            return Some(corruption(CanaryRegion::Tail, offset));
+        }
+    }
+    None
+}
+
+/// Allocates `layout` with canary redzones on both sides and tracks it as live, called from
+/// `RuntimeAllocator::alloc` in place of the backend's own allocation
+pub(crate) unsafe fn alloc(layout: Layout) -> *mut u8 {
+    let head = head_redzone(layout.align());
+    let Some(outer) = outer_layout(layout) else {
+        return core::ptr::null_mut();
+    };
+    let base = RuntimeAllocator::backend_alloc(outer);
+    if base.is_null() {
+        return base;
+    }
+
+    core::ptr::write_bytes(base.add(HEADER_SIZE), CANARY, head - HEADER_SIZE);
+    core::ptr::write_bytes(base.add(head + layout.size()), CANARY, TAIL_REDZONE);
+
+    let header = base as *mut Header;
+    let mut live = live();
+    header.write(Header { prev: 0, next: live.first, size: layout.size(), align: layout.align() });
+    if live.first != 0 {
+        (*(live.first as *mut Header)).prev = header as usize;
+    }
+    live.first = header as usize;
+    live.blocks += 1;
+    live.bytes += layout.size();
+    drop(live);
+
+    base.add(head)
+}
+
+/// Validates and untracks a block, called from `RuntimeAllocator::dealloc`
+///
+/// Aborts with a report naming the layout and the caller's backtrace if either redzone was
+/// overwritten. The memory itself is returned later through [`underlying()`].
+pub(crate) unsafe fn release(ptr: *mut u8, layout: Layout) {
+    let header = ptr.sub(head_redzone(layout.align())) as *mut Header;
+
+    let damage = check_block(header).or_else(|| {
+        // Intact canaries but a different layout: the header itself was overwritten
+        let header_intact = (*header).size == layout.size() && (*header).align == layout.align();
+        (!header_intact).then_some(HeapCorruption {
+            address: ptr as usize,
+            size: layout.size(),
+            align: layout.align(),
+            region: CanaryRegion::Head,
+            offset: head_redzone(layout.align()) - HEADER_SIZE,
+        })
+    });
+    if let Some(corruption) = damage {
+        report_corruption(&corruption, true);
+        std::process::abort();
+    }
+
+    let mut live = live();
+    let (prev, next) = ((*header).prev, (*header).next);
+    if prev != 0 {
+        (*(prev as *mut Header)).next = next;
+    } else {
+        live.first = next;
+    }
+    if next != 0 {
+        (*(next as *mut Header)).prev = prev;
+    }
+    live.blocks -= 1;
+    live.bytes -= layout.size();
+    drop(live);
+}
+
+/// Maps a block handed out by [`alloc()`] back to the allocation made from the backend
+pub(crate) unsafe fn underlying(ptr: *mut u8, layout: Layout) -> (*mut u8, Layout) {
+    // outer_layout() succeeded when this block was allocated
+    (ptr.sub(head_redzone(layout.align())), outer_layout(layout).unwrap_unchecked())
+}
+
+fn outer_layout(layout: Layout) -> Option<Layout> {
+    let size = head_redzone(layout.align())
+        .checked_add(layout.size())?
+        .checked_add(TAIL_REDZONE)?;
+    // Backends only honour the requested alignment, and the header needs its own
+    let align = layout.align().max(core::mem::align_of::<Header>());
+    Layout::from_size_align(size, align).ok()
+}
+
+#[cold]
+fn report_corruption(corruption: &HeapCorruption, fatal: bool) {
+    let mut message = StackBuffer::<256>::new();
+    let _ = writeln!(
+        message,
+        "[{}] Auto-allocator: {}, detected at:",
+        if fatal { "FATAL" } else { "ERROR" },
+        corruption
+    );
+    write_stderr(message.as_bytes());
+    write_backtrace();
+}
+
+/// Checks the redzones of every live block
+///
+/// Walks all blocks currently allocated through the global allocator and returns the first
+/// one whose canaries were overwritten, after writing its layout and the caller's backtrace
+/// to stderr. Unlike a smashed canary found on deallocation, this does not abort, so a
+/// service can call it periodically or from a debug endpoint and decide how to react.
+///
+/// Takes the tracking lock for the duration of the walk, stalling allocation on other threads.
+///
+/// # Example
+///
+/// ```rust
+/// let buffer = vec![0u8; 64];
+/// let check = auto_allocator::verify_heap().expect("heap corrupted");
+/// assert!(check.blocks >= 1);
+/// drop(buffer);
+/// ```
+pub fn verify_heap() -> Result<HeapCheck, HeapCorruption> {
+    let live = live();
+    let mut header = live.first as *const Header;
+    let mut damage = None;
+    while !header.is_null() {
+        damage = unsafe { check_block(header) };
+        if damage.is_some() {
+            break;
+        }
+        header = unsafe { (*header).next } as *const Header;
+    }
+    let check = HeapCheck { blocks: live.blocks, bytes: live.bytes };
+    drop(live);
+
+    match damage {
+        Some(corruption) => {
+            report_corruption(&corruption, false);
+            Err(corruption)
+        }
+        None => Ok(check),
+    }
+}
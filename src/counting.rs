@@ -0,0 +1,100 @@
+use core::alloc::Layout;
+use core::cell::Cell;
+use crate::reentrancy::ReentrancyGuard;
+// ========== Scoped Allocation Counting ==========
+
+/// Allocation activity observed on one thread
+///
+/// Returned by [`count_allocations()`]. Reallocations that move a block count as one
+/// allocation plus one free.
+///
+/// # Example
+///
+/// ```rust
+/// let counts = auto_allocator::count_allocations(|| {
+///     let data = std::hint::black_box(vec![0u8; 128]);
+///     drop(data);
+/// });
+/// assert_eq!(counts.allocations, 1);
+/// assert_eq!(counts.bytes, 128);
+/// assert_eq!(counts.frees, 1);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AllocCounts {
+    /// Number of successful allocations
+    pub allocations: u64,
+
+    /// Total bytes requested by those allocations
+    pub bytes: u64,
+
+    /// Number of deallocations
+    pub frees: u64,
+}
+
+thread_local! {
+    static THREAD_COUNTS: Cell<AllocCounts> = const {
+        Cell::new(AllocCounts { allocations: 0, bytes: 0, frees: 0 })
+    };
+}
+
+/// Counts a successful allocation, called from `RuntimeAllocator::alloc`
+#[inline]
+pub(crate) fn record_alloc(ptr: *mut u8, layout: Layout) {
+    if ptr.is_null() || ReentrancyGuard::is_held() {
+        return;
+    }
+    let _ = THREAD_COUNTS.try_with(|counts| {
+        let mut current = counts.get();
+        current.allocations += 1;
+        current.bytes += layout.size() as u64;
+        counts.set(current);
+    });
+}
+
+/// Counts a deallocation, called from `RuntimeAllocator::dealloc`
+#[inline]
+pub(crate) fn record_dealloc() {
+    if ReentrancyGuard::is_held() {
+        return;
+    }
+    let _ = THREAD_COUNTS.try_with(|counts| {
+        let mut current = counts.get();
+        current.frees += 1;
+        counts.set(current);
+    });
+}
+
+fn thread_counts() -> AllocCounts {
+    THREAD_COUNTS.try_with(Cell::get).unwrap_or_default()
+}
+
+/// Counts the allocations, bytes and frees made on the current thread while running `f`
+///
+/// Only the calling thread is observed; work handed off to other threads is not included.
+/// Calls can be nested, in which case the outer count includes the inner one. Allocations
+/// made internally by other auto-allocator diagnostics are never counted.
+///
+/// # Example
+///
+/// ```rust
+/// // Assert that a hot path stays within its allocation budget
+/// let counts = auto_allocator::count_allocations(|| {
+///     let mut out = String::with_capacity(64);
+///     for word in ["fast", "path"] {
+///         out.push_str(word);
+///     }
+///     std::hint::black_box(out);
+/// });
+/// assert!(counts.allocations <= 1);
+/// ```
+pub fn count_allocations<F: FnOnce()>(f: F) -> AllocCounts {
+    let before = thread_counts();
+    f();
+    let after = thread_counts();
+
+    AllocCounts {
+        allocations: after.allocations - before.allocations,
+        bytes: after.bytes - before.bytes,
+        frees: after.frees - before.frees,
+    }
+}
@@ -0,0 +1,135 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+use crate::types::DebuggingTool;
+// ========== Debugging Tool Detection ==========
+
+/// Detected tool, 0 until detection ran, 1 when none was found
+static DETECTED: AtomicU8 = AtomicU8::new(0);
+
+const fn encode(tool: Option<DebuggingTool>) -> u8 {
+    match tool {
+        None => 1,
+        Some(DebuggingTool::AddressSanitizer) => 2,
+        Some(DebuggingTool::ThreadSanitizer) => 3,
+        Some(DebuggingTool::Valgrind) => 4,
+        Some(DebuggingTool::Miri) => 5,
+    }
+}
+
+const fn decode(value: u8) -> Option<DebuggingTool> {
+    match value {
+        2 => Some(DebuggingTool::AddressSanitizer),
+        3 => Some(DebuggingTool::ThreadSanitizer),
+        4 => Some(DebuggingTool::Valgrind),
+        5 => Some(DebuggingTool::Miri),
+        _ => None,
+    }
+}
+
+/// Name of a tool as used in selection reasons
+pub(crate) const fn tool_name(tool: DebuggingTool) -> &'static str {
+    match tool {
+        DebuggingTool::AddressSanitizer => "AddressSanitizer",
+        DebuggingTool::ThreadSanitizer => "ThreadSanitizer",
+        DebuggingTool::Valgrind => "Valgrind",
+        DebuggingTool::Miri => "Miri",
+    }
+}
+
+/// Sanitizer compiled in with `-Zsanitizer` (reported by the build script) or preloaded into a
+/// C or C++ host as a shared library
+fn sanitizer() -> Option<DebuggingTool> {
+    if cfg!(auto_allocator_sanitize = "address") {
+        return Some(DebuggingTool::AddressSanitizer);
+    }
+    if cfg!(auto_allocator_sanitize = "thread") {
+        return Some(DebuggingTool::ThreadSanitizer);
+    }
+
+    #[cfg(unix)]
+    {
+        let contains = |text: &[u8], needle: &[u8]| text.windows(needle.len()).any(|window| window == needle);
+        crate::platform::with_env_var(c"LD_PRELOAD", |value| {
+            if contains(value, b"libasan") || contains(value, b"libclang_rt.asan") {
+                Some(DebuggingTool::AddressSanitizer)
+            } else if contains(value, b"libtsan") || contains(value, b"libclang_rt.tsan") {
+                Some(DebuggingTool::ThreadSanitizer)
+            } else {
+                None
+            }
+        })
+        .flatten()
+    }
+
+    #[cfg(not(unix))]
+    None
+}
+
+/// Valgrind's `RUNNING_ON_VALGRIND` client request: a rotation sequence that is a no-op on the
+/// CPU, which Valgrind recognizes and answers with a non-zero value
+#[cfg(all(target_arch = "x86_64", not(miri), not(windows)))]
+fn running_on_valgrind() -> bool {
+    const VG_USERREQ_RUNNING_ON_VALGRIND: u64 = 0x1001;
+    let request: [u64; 6] = [VG_USERREQ_RUNNING_ON_VALGRIND, 0, 0, 0, 0, 0];
+    let result: u64;
+    unsafe {
+        // rol rdi,3; rol rdi,13; rol rdi,61; rol rdi,51; xchg rbx,rbx - spelled out as bytes so
+        // the encoding matches valgrind.h exactly
+        core::arch::asm!(
+            ".byte 0x48, 0xc1, 0xc7, 0x03, 0x48, 0xc1, 0xc7, 0x0d",
+            ".byte 0x48, 0xc1, 0xc7, 0x3d, 0x48, 0xc1, 0xc7, 0x33",
+            ".byte 0x48, 0x87, 0xdb",
+            in("rax") request.as_ptr(),
+            inout("rdx") 0u64 => result,
+            out("rdi") _,
+            options(nostack)
+        );
+    }
+    result != 0
+}
+
+/// Valgrind's `RUNNING_ON_VALGRIND` client request: a rotation sequence that is a no-op on the
+/// CPU, which Valgrind recognizes and answers with a non-zero value
+#[cfg(all(target_arch = "aarch64", not(miri), not(windows)))]
+fn running_on_valgrind() -> bool {
+    const VG_USERREQ_RUNNING_ON_VALGRIND: u64 = 0x1001;
+    let request: [u64; 6] = [VG_USERREQ_RUNNING_ON_VALGRIND, 0, 0, 0, 0, 0];
+    let result: u64;
+    unsafe {
+        core::arch::asm!(
+            "ror x12, x12, #3",
+            "ror x12, x12, #13",
+            "ror x12, x12, #51",
+            "ror x12, x12, #61",
+            "orr x10, x10, x10",
+            in("x4") request.as_ptr(),
+            inout("x3") 0u64 => result,
+            out("x12") _,
+            options(nostack)
+        );
+    }
+    result != 0
+}
+
+#[cfg(not(all(any(target_arch = "x86_64", target_arch = "aarch64"), not(miri), not(windows))))]
+fn running_on_valgrind() -> bool {
+    false
+}
+
+/// Debugging tool the process runs under, detected once without allocating
+///
+/// Each of them only sees heap blocks that come from the system allocator: sanitizers and
+/// Valgrind intercept `malloc`, and Miri cannot execute mimalloc's C code.
+pub(crate) fn detect() -> Option<DebuggingTool> {
+    let detected = DETECTED.load(Ordering::Acquire);
+    if detected != 0 {
+        return decode(detected);
+    }
+
+    let tool = if cfg!(miri) {
+        Some(DebuggingTool::Miri)
+    } else {
+        sanitizer().or_else(|| running_on_valgrind().then_some(DebuggingTool::Valgrind))
+    };
+    DETECTED.store(encode(tool), Ordering::Release);
+    tool
+}
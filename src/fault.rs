@@ -0,0 +1,210 @@
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use crate::reentrancy::ReentrancyGuard;
+// ========== Allocation Fault Injection ==========
+
+/// Schedule deciding which allocations are made to fail
+///
+/// Failed allocations return null from `RuntimeAllocator::alloc`, exactly like a real
+/// out-of-memory condition: `try_reserve` reports an error and infallible collections go
+/// through the alloc error handler.
+///
+/// # Example
+///
+/// ```rust
+/// use auto_allocator::FailureSchedule;
+///
+/// let result = auto_allocator::with_injected_failures(FailureSchedule::LargerThan(1024), || {
+///     let mut buffer: Vec<u8> = Vec::new();
+///     buffer.try_reserve(4096)
+/// });
+/// assert!(result.is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FailureSchedule {
+    /// Never fail an allocation (default)
+    Never,
+
+    /// Fail only the Nth allocation (1-based), counted from when the schedule was installed
+    Nth(u64),
+
+    /// Fail every allocation larger than the given number of bytes
+    LargerThan(usize),
+
+    /// Fail a random fraction of allocations, reproducibly for a given seed
+    Random {
+        /// Probability in `0.0..=1.0` that an allocation fails
+        probability: f64,
+        /// Seed for the deterministic pseudo-random sequence
+        seed: u64,
+    },
+}
+
+/// A schedule together with its progress (allocations seen, random state)
+#[derive(Clone, Copy)]
+struct ScheduleState {
+    schedule: FailureSchedule,
+    seen: u64,
+    rng: u64,
+}
+
+impl ScheduleState {
+    const fn new(schedule: FailureSchedule) -> Self {
+        let rng = match schedule {
+            FailureSchedule::Random { seed, .. } => seed,
+            _ => 0,
+        };
+        Self { schedule, seen: 0, rng }
+    }
+
+    fn should_fail(&mut self, layout: Layout) -> bool {
+        self.seen += 1;
+        match self.schedule {
+            FailureSchedule::Never => false,
+            FailureSchedule::Nth(n) => self.seen == n,
+            FailureSchedule::LargerThan(limit) => layout.size() > limit,
+            FailureSchedule::Random { probability, .. } => {
+                self.rng = self.rng.wrapping_add(SPLITMIX_GAMMA);
+                splitmix64(self.rng) < probability_threshold(probability)
+            }
+        }
+    }
+}
+
+const SPLITMIX_GAMMA: u64 = 0x9E37_79B9_7F4A_7C15;
+
+fn splitmix64(state: u64) -> u64 {
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Maps a probability onto the `u64` range so a uniform draw can be compared against it
+fn probability_threshold(probability: f64) -> u64 {
+    if probability >= 1.0 {
+        u64::MAX
+    } else if probability > 0.0 {
+        (probability * u64::MAX as f64) as u64
+    } else {
+        0
+    }
+}
+
+// Process-wide schedule, stored as atomics so the hot path never takes a lock.
+// Kind: 0=never, 1=nth, 2=larger-than, 3=random
+static GLOBAL_KIND: AtomicU8 = AtomicU8::new(0);
+static GLOBAL_PARAM: AtomicU64 = AtomicU64::new(0);
+static GLOBAL_SEEN: AtomicU64 = AtomicU64::new(0);
+static GLOBAL_RNG: AtomicU64 = AtomicU64::new(0);
+static INJECTED_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static THREAD_SCHEDULE: Cell<Option<ScheduleState>> = const { Cell::new(None) };
+}
+
+/// Installs a process-wide failure schedule for allocations on every thread
+///
+/// A schedule installed on a thread with [`inject_failures()`] takes precedence on that
+/// thread. Installing a schedule restarts its allocation count.
+pub fn set_failure_schedule(schedule: FailureSchedule) {
+    // Disable first so concurrent allocations never see half-written parameters
+    GLOBAL_KIND.store(0, Ordering::SeqCst);
+    GLOBAL_SEEN.store(0, Ordering::SeqCst);
+    let (kind, param) = match schedule {
+        FailureSchedule::Never => (0, 0),
+        FailureSchedule::Nth(n) => (1, n),
+        FailureSchedule::LargerThan(limit) => (2, limit as u64),
+        FailureSchedule::Random { probability, seed } => {
+            GLOBAL_RNG.store(seed, Ordering::SeqCst);
+            (3, probability_threshold(probability))
+        }
+    };
+    GLOBAL_PARAM.store(param, Ordering::SeqCst);
+    GLOBAL_KIND.store(kind, Ordering::SeqCst);
+}
+
+/// Returns how many allocations have been failed on purpose in this process
+pub fn injected_failure_count() -> u64 {
+    INJECTED_FAILURES.load(Ordering::Relaxed)
+}
+
+/// Guard that applies a failure schedule to the current thread until dropped
+///
+/// Created by [`inject_failures()`]. Dropping it restores the thread's previous schedule.
+#[must_use = "failures are only injected while the guard is alive"]
+pub struct FaultInjectionGuard {
+    previous: Option<ScheduleState>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for FaultInjectionGuard {
+    fn drop(&mut self) {
+        let previous = self.previous;
+        let _ = THREAD_SCHEDULE.try_with(|state| state.set(previous));
+    }
+}
+
+/// Applies `schedule` to allocations on the current thread for as long as the guard lives
+///
+/// Other threads are unaffected, which keeps test harness threads out of the failure path.
+pub fn inject_failures(schedule: FailureSchedule) -> FaultInjectionGuard {
+    let previous = THREAD_SCHEDULE
+        .try_with(|state| state.replace(Some(ScheduleState::new(schedule))))
+        .unwrap_or(None);
+    FaultInjectionGuard { previous, _not_send: PhantomData }
+}
+
+/// Runs `f` with `schedule` applied to allocations on the current thread
+///
+/// Closure form of [`inject_failures()`].
+pub fn with_injected_failures<R, F: FnOnce() -> R>(schedule: FailureSchedule, f: F) -> R {
+    let _guard = inject_failures(schedule);
+    f()
+}
+
+/// Decides whether an allocation should be failed, called from `RuntimeAllocator::alloc`
+#[inline]
+pub(crate) fn should_fail(layout: Layout) -> bool {
+    if ReentrancyGuard::is_held() {
+        return false;
+    }
+
+    let thread_decision = THREAD_SCHEDULE
+        .try_with(|cell| {
+            cell.get().map(|mut state| {
+                let fail = state.should_fail(layout);
+                cell.set(Some(state));
+                fail
+            })
+        })
+        .ok()
+        .flatten();
+
+    let fail = match thread_decision {
+        Some(fail) => fail,
+        None => global_should_fail(layout),
+    };
+
+    if fail {
+        INJECTED_FAILURES.fetch_add(1, Ordering::Relaxed);
+    }
+    fail
+}
+
+#[inline]
+fn global_should_fail(layout: Layout) -> bool {
+    match GLOBAL_KIND.load(Ordering::Acquire) {
+        0 => false,
+        1 => GLOBAL_SEEN.fetch_add(1, Ordering::Relaxed) + 1 == GLOBAL_PARAM.load(Ordering::Relaxed),
+        2 => layout.size() as u64 > GLOBAL_PARAM.load(Ordering::Relaxed),
+        _ => {
+            let state = GLOBAL_RNG
+                .fetch_add(SPLITMIX_GAMMA, Ordering::Relaxed)
+                .wrapping_add(SPLITMIX_GAMMA);
+            splitmix64(state) < GLOBAL_PARAM.load(Ordering::Relaxed)
+        }
+    }
+}
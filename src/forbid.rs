@@ -0,0 +1,149 @@
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::fmt::Write as _;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use crate::rawlog::{write_stderr, StackBuffer};
+use crate::reentrancy::ReentrancyGuard;
+// ========== Allocation-forbidden Regions ==========
+
+/// What happens when a thread allocates inside a forbidden region
+///
+/// Set process-wide with [`set_forbidden_allocation_action()`]. Every violation is counted
+/// in [`forbidden_allocation_count()`] regardless of the action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForbiddenAllocationAction {
+    /// Write a message to stderr and abort the process (default)
+    Abort,
+
+    /// Write a warning to stderr and let the allocation proceed
+    Log,
+
+    /// Only count the violation and let the allocation proceed
+    Count,
+}
+
+thread_local! {
+    static DENY_DEPTH: Cell<u32> = const { Cell::new(0) };
+}
+
+static ACTION: AtomicU8 = AtomicU8::new(0);
+static VIOLATIONS: AtomicU64 = AtomicU64::new(0);
+
+impl ForbiddenAllocationAction {
+    const fn to_u8(self) -> u8 {
+        match self {
+            ForbiddenAllocationAction::Abort => 0,
+            ForbiddenAllocationAction::Log => 1,
+            ForbiddenAllocationAction::Count => 2,
+        }
+    }
+
+    const fn from_u8(value: u8) -> Self {
+        match value {
+            1 => ForbiddenAllocationAction::Log,
+            2 => ForbiddenAllocationAction::Count,
+            _ => ForbiddenAllocationAction::Abort,
+        }
+    }
+}
+
+/// Guard that forbids allocations on the current thread until dropped
+///
+/// Created by [`deny_allocations()`]. Guards nest, and the guard cannot be sent to another
+/// thread because it only affects the thread that created it.
+#[must_use = "allocations are only forbidden while the guard is alive"]
+pub struct DenyAllocationsGuard {
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for DenyAllocationsGuard {
+    fn drop(&mut self) {
+        let _ = DENY_DEPTH.try_with(|depth| depth.set(depth.get().saturating_sub(1)));
+    }
+}
+
+/// Forbids allocations on the current thread for as long as the returned guard lives
+///
+/// Intended for real-time threads (audio callbacks, control loops) that must never touch
+/// the heap. Any allocation while the guard is active triggers the configured
+/// [`ForbiddenAllocationAction`]. Deallocations are still allowed.
+///
+/// # Example
+///
+/// ```rust
+/// let buffer = vec![0.0f32; 256];
+///
+/// let _guard = auto_allocator::deny_allocations();
+/// let peak = buffer.iter().fold(0.0f32, |acc, &x| acc.max(x.abs()));
+/// assert_eq!(peak, 0.0);
+/// ```
+pub fn deny_allocations() -> DenyAllocationsGuard {
+    let _ = DENY_DEPTH.try_with(|depth| depth.set(depth.get() + 1));
+    DenyAllocationsGuard { _not_send: PhantomData }
+}
+
+/// Runs `f` with allocations forbidden on the current thread
+///
+/// Closure form of [`deny_allocations()`]; the region ends when `f` returns or unwinds.
+///
+/// # Example
+///
+/// ```rust
+/// let samples = [0.25f32, -0.5, 0.75];
+/// let sum: f32 = auto_allocator::forbid_allocations(|| samples.iter().sum());
+/// assert_eq!(sum, 0.5);
+/// ```
+pub fn forbid_allocations<R, F: FnOnce() -> R>(f: F) -> R {
+    let _guard = deny_allocations();
+    f()
+}
+
+/// Sets the process-wide action taken when a forbidden allocation happens
+pub fn set_forbidden_allocation_action(action: ForbiddenAllocationAction) {
+    ACTION.store(action.to_u8(), Ordering::Relaxed);
+}
+
+/// Returns the process-wide action taken when a forbidden allocation happens
+pub fn forbidden_allocation_action() -> ForbiddenAllocationAction {
+    ForbiddenAllocationAction::from_u8(ACTION.load(Ordering::Relaxed))
+}
+
+/// Returns how many forbidden allocations have happened in this process
+pub fn forbidden_allocation_count() -> u64 {
+    VIOLATIONS.load(Ordering::Relaxed)
+}
+
+/// Checks an allocation request against the current thread's forbidden region, called
+/// from `RuntimeAllocator::alloc` before the backend is asked for memory
+#[inline]
+pub(crate) fn check_allocation(layout: Layout) {
+    let denied = DENY_DEPTH.try_with(|depth| depth.get() > 0).unwrap_or(false);
+    if denied && !ReentrancyGuard::is_held() {
+        report_violation(layout);
+    }
+}
+
+#[cold]
+fn report_violation(layout: Layout) {
+    VIOLATIONS.fetch_add(1, Ordering::Relaxed);
+
+    let action = forbidden_allocation_action();
+    if action == ForbiddenAllocationAction::Count {
+        return;
+    }
+
+    let mut message = StackBuffer::<192>::new();
+    let _ = writeln!(
+        message,
+        "[{}] Auto-allocator: allocation of {} bytes (align {}) in allocation-forbidden region",
+        if action == ForbiddenAllocationAction::Abort { "FATAL" } else { "WARN" },
+        layout.size(),
+        layout.align()
+    );
+    write_stderr(message.as_bytes());
+
+    if action == ForbiddenAllocationAction::Abort {
+        std::process::abort();
+    }
+}
@@ -138,3 +138,14 @@ pub fn format_memory_size(bytes: u64) -> &'static str {
 }
 
 // ========== Platform Detection ==========
+
+/// Formats the NUMA part of a hardware summary: `", 2 NUMA nodes"` on multi-node systems,
+/// nothing on single-node ones
+#[cfg(not(target_os = "none"))]
+pub(crate) fn format_numa_nodes(node_count: usize) -> String {
+    if node_count > 1 {
+        format!(", {} NUMA nodes", node_count)
+    } else {
+        String::new()
+    }
+}
@@ -0,0 +1,99 @@
+use core::ffi::CStr;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use crate::lowmem::{classify, MemoryClass};
+use crate::platform::with_env_var;
+use crate::system::{get_effective_cpu_cores, get_effective_memory_limit};
+use crate::types::GlibcTuning;
+// ========== glibc malloc Tuning ==========
+
+/// Fixed `mmap` threshold; glibc starts at 128 KiB and may raise it up to 32 MiB
+const MMAP_THRESHOLD: usize = 256 << 10;
+
+/// Heap top kept before trimming, glibc's default is 128 KiB until the `mmap` threshold moves
+const TRIM_THRESHOLD: usize = 1 << 20;
+
+/// `mmap` threshold on hosts short on memory, glibc's own starting value
+const CONSTRAINED_MMAP_THRESHOLD: usize = 128 << 10;
+
+/// Trim threshold on hosts short on memory
+const CONSTRAINED_TRIM_THRESHOLD: usize = 256 << 10;
+
+static APPLIED: AtomicBool = AtomicBool::new(false);
+
+/// Values set with `mallopt`, 0 for parameters left to glibc
+static ARENA_MAX: AtomicUsize = AtomicUsize::new(0);
+static TRIM: AtomicUsize = AtomicUsize::new(0);
+static MMAP: AtomicUsize = AtomicUsize::new(0);
+
+/// Whether `AUTO_ALLOCATOR_GLIBC_TUNING` leaves tuning on (`auto`, the default) or not (`off`)
+fn enabled() -> bool {
+    with_env_var(c"AUTO_ALLOCATOR_GLIBC_TUNING", |value| value != b"off").unwrap_or(true)
+}
+
+/// Whether the environment already sets a parameter, through its `MALLOC_*` variable or
+/// `GLIBC_TUNABLES`
+fn set_by_environment(variable: &CStr, tunable: &[u8]) -> bool {
+    with_env_var(variable, |_| ()).is_some()
+        || with_env_var(c"GLIBC_TUNABLES", |value| {
+            value.windows(tunable.len()).any(|window| window == tunable)
+        })
+        .unwrap_or(false)
+}
+
+/// Sets one parameter unless the environment already does, recording what was set
+fn set(param: libc::c_int, value: usize, variable: &CStr, tunable: &[u8], slot: &AtomicUsize) {
+    if set_by_environment(variable, tunable) {
+        return;
+    }
+    if unsafe { libc::mallopt(param, value as libc::c_int) } == 1 {
+        slot.store(value, Ordering::Relaxed);
+    }
+}
+
+/// Applies `mallopt` settings when the glibc system allocator was selected, called once right
+/// after selection
+pub(crate) fn configure_backend(allocator_id: u8) {
+    if allocator_id != 1 || !enabled() {
+        return;
+    }
+
+    let (mmap_threshold, trim_threshold) = match classify(get_effective_memory_limit()) {
+        MemoryClass::Ample => (MMAP_THRESHOLD, TRIM_THRESHOLD),
+        _ => (CONSTRAINED_MMAP_THRESHOLD, CONSTRAINED_TRIM_THRESHOLD),
+    };
+    set(
+        libc::M_ARENA_MAX,
+        get_effective_cpu_cores().max(2),
+        c"MALLOC_ARENA_MAX",
+        b"glibc.malloc.arena_max",
+        &ARENA_MAX,
+    );
+    set(
+        libc::M_MMAP_THRESHOLD,
+        mmap_threshold,
+        c"MALLOC_MMAP_THRESHOLD_",
+        b"glibc.malloc.mmap_threshold",
+        &MMAP,
+    );
+    set(
+        libc::M_TRIM_THRESHOLD,
+        trim_threshold,
+        c"MALLOC_TRIM_THRESHOLD_",
+        b"glibc.malloc.trim_threshold",
+        &TRIM,
+    );
+    APPLIED.store(true, Ordering::Release);
+}
+
+/// Settings applied by [`configure_backend`], `None` when tuning did not run
+pub(crate) fn applied() -> Option<GlibcTuning> {
+    if !APPLIED.load(Ordering::Acquire) {
+        return None;
+    }
+    let value = |slot: &AtomicUsize| Some(slot.load(Ordering::Relaxed)).filter(|&value| value != 0);
+    Some(GlibcTuning {
+        arena_max: value(&ARENA_MAX),
+        trim_threshold: value(&TRIM),
+        mmap_threshold: value(&MMAP),
+    })
+}
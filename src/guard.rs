@@ -0,0 +1,141 @@
+use core::alloc::{GlobalAlloc, Layout};
+#[cfg(feature = "debug-guard")]
+use core::sync::atomic::{AtomicU8, Ordering};
+use crate::platform::get_page_size_safe;
+#[cfg(feature = "debug-guard")]
+use crate::platform::with_env_var;
+// ========== Guard-page Debugging Backend ==========
+
+/// Which side of each allocation the inaccessible guard page sits on
+///
+/// Read once from `AUTO_ALLOCATOR_GUARD_MODE` (`overflow` or `underflow`) when the backend
+/// first allocates, and fixed for the rest of the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(feature = "debug-guard"), allow(dead_code))]
+pub enum GuardMode {
+    /// Allocation ends right at the guard page, catching accesses past its end (default)
+    Overflow,
+
+    /// Allocation starts right after the guard page, catching accesses before its start
+    Underflow,
+}
+
+// 0=unresolved, 1=overflow, 2=underflow
+#[cfg(feature = "debug-guard")]
+static MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Returns the guard placement used by the `debug-guard` backend
+///
+/// The backend itself is chosen with `AUTO_ALLOCATOR_BACKEND=debug-guard`; this function only
+/// reports where the guard pages go.
+#[cfg(feature = "debug-guard")]
+pub fn guard_mode() -> GuardMode {
+    if MODE.load(Ordering::Relaxed) == 0 {
+        let underflow = with_env_var(c"AUTO_ALLOCATOR_GUARD_MODE", |value| value == b"underflow")
+            .unwrap_or(false);
+        // First resolution wins, so alloc and dealloc always agree on the layout
+        let mode = if underflow { 2 } else { 1 };
+        let _ = MODE.compare_exchange(0, mode, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    match MODE.load(Ordering::Relaxed) {
+        2 => GuardMode::Underflow,
+        _ => GuardMode::Overflow,
+    }
+}
+
+/// Placement of an allocation inside its private mapping
+struct Geometry {
+    map_len: usize,
+    user_offset: usize,
+    guard_offset: usize,
+}
+
+fn round_up(value: usize, multiple: usize) -> Option<usize> {
+    Some(value.checked_add(multiple - 1)? & !(multiple - 1))
+}
+
+/// Computes the mapping for `layout`; a pure function of layout, page size and mode, so
+/// `dealloc` can find the mapping again without storing any metadata
+fn geometry(layout: Layout, page: usize, mode: GuardMode) -> Option<Geometry> {
+    let size = layout.size().max(1);
+    match mode {
+        GuardMode::Overflow => {
+            // Only the padding needed for alignment separates the end from the guard page
+            let rounded = round_up(size, layout.align())?;
+            let data_len = round_up(rounded, page)?;
+            Some(Geometry {
+                map_len: data_len.checked_add(page)?,
+                user_offset: data_len - rounded,
+                guard_offset: data_len,
+            })
+        }
+        GuardMode::Underflow => {
+            let data_len = round_up(size, page)?;
+            Some(Geometry {
+                map_len: data_len.checked_add(page)?,
+                user_offset: page,
+                guard_offset: 0,
+            })
+        }
+    }
+}
+
+/// Allocates through the `debug-guard` backend, placing the guard as [`guard_mode()`] says
+#[cfg(feature = "debug-guard")]
+pub(crate) unsafe fn alloc(layout: Layout) -> *mut u8 {
+    map_guarded(layout, guard_mode())
+}
+
+/// Frees a block allocated by [`alloc()`]
+#[cfg(feature = "debug-guard")]
+pub(crate) unsafe fn dealloc(ptr: *mut u8, layout: Layout) {
+    unmap_guarded(ptr, layout, guard_mode())
+}
+
+/// Maps fresh pages for one allocation and makes the neighbouring page inaccessible
+///
+/// Alignments above the page size cannot be honoured by `mmap` alone and are served by the
+/// system allocator without a guard.
+pub(crate) unsafe fn map_guarded(layout: Layout, mode: GuardMode) -> *mut u8 {
+    let page = get_page_size_safe();
+    if layout.align() > page {
+        return std::alloc::System.alloc(layout);
+    }
+    let Some(geometry) = geometry(layout, page, mode) else {
+        return core::ptr::null_mut();
+    };
+
+    let base = libc::mmap(
+        core::ptr::null_mut(),
+        geometry.map_len,
+        libc::PROT_READ | libc::PROT_WRITE,
+        libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+        -1,
+        0,
+    );
+    if base == libc::MAP_FAILED {
+        return core::ptr::null_mut();
+    }
+
+    let base = base as *mut u8;
+    let guard = base.add(geometry.guard_offset) as *mut libc::c_void;
+    if libc::mprotect(guard, page, libc::PROT_NONE) != 0 {
+        libc::munmap(base as *mut libc::c_void, geometry.map_len);
+        return core::ptr::null_mut();
+    }
+    base.add(geometry.user_offset)
+}
+
+/// Unmaps an allocation together with its guard page
+///
+/// Any later access through a dangling pointer faults until the address range is reused.
+pub(crate) unsafe fn unmap_guarded(ptr: *mut u8, layout: Layout, mode: GuardMode) {
+    let page = get_page_size_safe();
+    if layout.align() > page {
+        return std::alloc::System.dealloc(ptr, layout);
+    }
+    if let Some(geometry) = geometry(layout, page, mode) {
+        libc::munmap(ptr.sub(geometry.user_offset) as *mut libc::c_void, geometry.map_len);
+    }
+}
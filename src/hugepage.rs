@@ -0,0 +1,73 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+use crate::system::get_transparent_huge_pages;
+use crate::types::TransparentHugePages;
+// ========== Huge Page Policy ==========
+
+/// Allocations at least this large are advised to use transparent huge pages
+///
+/// The PMD-level huge page size on x86_64 and 4K-granule aarch64; smaller blocks cannot contain
+/// a whole huge page.
+pub(crate) const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+// 0=unresolved, 1=always, 2=madvise, 3=never, 4=unsupported
+static THP_MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Returns the transparent huge page mode, read once and cached
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn thp_mode() -> TransparentHugePages {
+    let code = match THP_MODE.load(Ordering::Relaxed) {
+        0 => {
+            let code = match get_transparent_huge_pages() {
+                TransparentHugePages::Always => 1,
+                TransparentHugePages::Madvise => 2,
+                TransparentHugePages::Never => 3,
+                TransparentHugePages::Unsupported => 4,
+            };
+            THP_MODE.store(code, Ordering::Relaxed);
+            code
+        }
+        code => code,
+    };
+
+    match code {
+        1 => TransparentHugePages::Always,
+        2 => TransparentHugePages::Madvise,
+        3 => TransparentHugePages::Never,
+        _ => TransparentHugePages::Unsupported,
+    }
+}
+
+/// Enables mimalloc's large OS pages when the kernel offers huge pages, called once right after
+/// the backend is selected and before it allocates
+pub(crate) fn configure_backend(allocator_id: u8) {
+    #[cfg(all(
+        any(feature = "_mimalloc", feature = "_mimalloc_secure"),
+        not(target_arch = "wasm32"),
+        not(debug_assertions)
+    ))]
+    if matches!(allocator_id, 2 | 5)
+        && matches!(thp_mode(), TransparentHugePages::Always | TransparentHugePages::Madvise)
+    {
+        unsafe { libmimalloc_sys::mi_option_set_enabled(libmimalloc_sys::mi_option_large_os_pages, true) };
+    }
+
+    let _ = allocator_id;
+}
+
+/// Advises the kernel to back the huge-page-aligned part of a large block with huge pages
+///
+/// Only needed in `madvise` mode: `always` already does this, `never` refuses it.
+#[cfg(target_os = "linux")]
+#[inline]
+pub(crate) unsafe fn advise(ptr: *mut u8, size: usize) {
+    if size < HUGE_PAGE_SIZE || ptr.is_null() || thp_mode() != TransparentHugePages::Madvise {
+        return;
+    }
+
+    let start = ptr.add(ptr.align_offset(HUGE_PAGE_SIZE));
+    let end = ptr as usize + size;
+    let len = end.saturating_sub(start as usize) & !(HUGE_PAGE_SIZE - 1);
+    if len > 0 {
+        libc::madvise(start as *mut libc::c_void, len, libc::MADV_HUGEPAGE);
+    }
+}
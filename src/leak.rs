@@ -0,0 +1,388 @@
+use core::alloc::Layout;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+use crate::reentrancy::ReentrancyGuard;
+// ========== Leak Report (DHAT-compatible) ==========
+
+/// Maximum number of return addresses recorded per allocation site
+const MAX_FRAMES: usize = 16;
+
+/// Frames belonging to the leak tracker itself (`capture_frames` and `record_alloc`)
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+const SKIPPED_FRAMES: usize = 2;
+
+type Frames = [usize; MAX_FRAMES];
+
+/// Per-site counters, mirroring the fields of a DHAT program point
+#[derive(Default, Clone, Copy)]
+struct SiteStats {
+    total_bytes: u64,
+    total_blocks: u64,
+    total_lifetimes_us: u64,
+    live_bytes: u64,
+    live_blocks: u64,
+    max_bytes: u64,
+    max_blocks: u64,
+    gmax_bytes: u64,
+    gmax_blocks: u64,
+}
+
+struct LiveBlock {
+    site: usize,
+    size: u64,
+    allocated_at_us: u64,
+}
+
+struct LeakTracker {
+    start: Instant,
+    sites: Vec<(Frames, SiteStats)>,
+    site_index: BTreeMap<Frames, usize>,
+    live: BTreeMap<usize, LiveBlock>,
+    live_bytes: u64,
+    max_live_bytes: u64,
+    gmax_at_us: u64,
+    // Per-site t-gmax values are only copied once the heap starts shrinking from a new peak,
+    // which keeps steadily growing workloads from paying an O(sites) snapshot per allocation
+    gmax_snapshot_pending: bool,
+}
+
+static TRACKER: Mutex<Option<LeakTracker>> = Mutex::new(None);
+static EXIT_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+impl LeakTracker {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            sites: Vec::new(),
+            site_index: BTreeMap::new(),
+            live: BTreeMap::new(),
+            live_bytes: 0,
+            max_live_bytes: 0,
+            gmax_at_us: 0,
+            gmax_snapshot_pending: false,
+        }
+    }
+
+    fn elapsed_us(&self) -> u64 {
+        self.start.elapsed().as_micros() as u64
+    }
+
+    fn on_alloc(&mut self, addr: usize, size: u64, frames: Frames) {
+        let now = self.elapsed_us();
+        let site = match self.site_index.get(&frames) {
+            Some(&index) => index,
+            None => {
+                self.sites.push((frames, SiteStats::default()));
+                self.site_index.insert(frames, self.sites.len() - 1);
+                self.sites.len() - 1
+            }
+        };
+
+        let stats = &mut self.sites[site].1;
+        stats.total_bytes += size;
+        stats.total_blocks += 1;
+        stats.live_bytes += size;
+        stats.live_blocks += 1;
+        if stats.live_bytes > stats.max_bytes {
+            stats.max_bytes = stats.live_bytes;
+            stats.max_blocks = stats.live_blocks;
+        }
+
+        self.live.insert(addr, LiveBlock { site, size, allocated_at_us: now });
+        self.live_bytes += size;
+        if self.live_bytes > self.max_live_bytes {
+            self.max_live_bytes = self.live_bytes;
+            self.gmax_at_us = now;
+            self.gmax_snapshot_pending = true;
+        }
+    }
+
+    fn on_dealloc(&mut self, addr: usize) {
+        let Some(block) = self.live.remove(&addr) else {
+            // Allocated before tracking started or by the tracker itself
+            return;
+        };
+
+        if self.gmax_snapshot_pending {
+            self.snapshot_gmax();
+        }
+
+        let now = self.elapsed_us();
+        let stats = &mut self.sites[block.site].1;
+        stats.live_bytes -= block.size;
+        stats.live_blocks -= 1;
+        stats.total_lifetimes_us += now.saturating_sub(block.allocated_at_us);
+        self.live_bytes -= block.size;
+    }
+
+    fn snapshot_gmax(&mut self) {
+        for (_, stats) in &mut self.sites {
+            stats.gmax_bytes = stats.live_bytes;
+            stats.gmax_blocks = stats.live_blocks;
+        }
+        self.gmax_snapshot_pending = false;
+    }
+
+    /// Serializes the sites that still own live blocks in DHAT's `dh_view.html` format
+    fn render_dhat_json(&mut self) -> (String, LeakSummary) {
+        if self.gmax_snapshot_pending {
+            self.snapshot_gmax();
+        }
+
+        let now = self.elapsed_us();
+        let mut summary = LeakSummary::default();
+        let mut frame_table: Vec<usize> = Vec::new();
+        let mut frame_index: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut program_points = String::new();
+
+        // Blocks still live at exit count their lifetime up to the end of the run
+        let mut live_lifetimes_us = vec![0u64; self.sites.len()];
+        for block in self.live.values() {
+            live_lifetimes_us[block.site] += now.saturating_sub(block.allocated_at_us);
+        }
+
+        for ((frames, stats), live_lifetimes) in self.sites.iter().zip(live_lifetimes_us) {
+            if stats.live_blocks == 0 {
+                continue;
+            }
+            summary.sites += 1;
+            summary.blocks += stats.live_blocks;
+            summary.bytes += stats.live_bytes;
+
+            let mut indices = String::new();
+            for &ip in frames.iter().take_while(|&&ip| ip != 0) {
+                let index = *frame_index.entry(ip).or_insert_with(|| {
+                    frame_table.push(ip);
+                    frame_table.len() // index 0 is "[root]"
+                });
+                let separator = if indices.is_empty() { "" } else { "," };
+                let _ = write!(indices, "{}{}", separator, index);
+            }
+
+            let separator = if program_points.is_empty() { "" } else { ",\n" };
+            let _ = write!(
+                program_points,
+                "{}{{\"tb\":{},\"tbk\":{},\"tl\":{},\"mb\":{},\"mbk\":{},\"gb\":{},\"gbk\":{},\"eb\":{},\"ebk\":{},\"fs\":[{}]}}",
+                separator,
+                stats.total_bytes,
+                stats.total_blocks,
+                stats.total_lifetimes_us + live_lifetimes,
+                stats.max_bytes,
+                stats.max_blocks,
+                stats.gmax_bytes,
+                stats.gmax_blocks,
+                stats.live_bytes,
+                stats.live_blocks,
+                indices
+            );
+        }
+
+        let mut frame_strings = String::from("\"[root]\"");
+        for ip in frame_table {
+            let _ = write!(frame_strings, ",\n\"{}\"", json_escape(&describe_frame(ip)));
+        }
+
+        let command: Vec<String> = std::env::args().collect();
+        let json = format!(
+            "{{\n\"dhatFileVersion\":2,\n\"mode\":\"rust-heap\",\n\"verb\":\"Allocated\",\n\
+             \"bklt\":true,\n\"bkacc\":false,\n\"tu\":\"µs\",\n\"Mtu\":\"s\",\n\"tuth\":10,\n\
+             \"cmd\":\"{}\",\n\"pid\":{},\n\"tg\":{},\n\"te\":{},\n\"pps\":[\n{}\n],\n\"ftbl\":[\n{}\n]\n}}\n",
+            json_escape(&command.join(" ")),
+            std::process::id(),
+            self.gmax_at_us,
+            now,
+            program_points,
+            frame_strings
+        );
+
+        (json, summary)
+    }
+}
+
+/// Summary of the allocations still live when a leak report was written
+///
+/// Returned by [`write_leak_report()`]; the full per-site breakdown is in the JSON file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LeakSummary {
+    /// Number of allocation sites that still own live blocks
+    pub sites: u64,
+
+    /// Number of live blocks across all sites
+    pub blocks: u64,
+
+    /// Number of live bytes across all sites
+    pub bytes: u64,
+}
+
+fn lock_tracker() -> MutexGuard<'static, Option<LeakTracker>> {
+    TRACKER.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Records a fresh allocation, called from `RuntimeAllocator::alloc`
+#[inline(never)]
+pub(crate) fn record_alloc(ptr: *mut u8, layout: Layout) {
+    if ptr.is_null() {
+        return;
+    }
+    let Some(_guard) = ReentrancyGuard::enter() else {
+        return;
+    };
+
+    let frames = capture_frames();
+    lock_tracker()
+        .get_or_insert_with(LeakTracker::new)
+        .on_alloc(ptr as usize, layout.size() as u64, frames);
+}
+
+/// Records a deallocation, called from `RuntimeAllocator::dealloc`
+#[inline]
+pub(crate) fn record_dealloc(ptr: *mut u8) {
+    let Some(_guard) = ReentrancyGuard::enter() else {
+        return;
+    };
+
+    if let Some(tracker) = lock_tracker().as_mut() {
+        tracker.on_dealloc(ptr as usize);
+    }
+}
+
+/// Captures return addresses of the allocating call stack without touching the global allocator
+#[inline(never)]
+fn capture_frames() -> Frames {
+    let mut frames: Frames = [0; MAX_FRAMES];
+
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    unsafe {
+        let mut raw = [core::ptr::null_mut::<libc::c_void>(); MAX_FRAMES + SKIPPED_FRAMES];
+        let depth = libc::backtrace(raw.as_mut_ptr(), raw.len() as libc::c_int).max(0) as usize;
+        for (slot, ip) in frames.iter_mut().zip(raw.iter().take(depth).skip(SKIPPED_FRAMES)) {
+            *slot = *ip as usize;
+        }
+    }
+
+    frames
+}
+
+/// Formats a frame as `0xADDR: symbol (object+0xOFFSET)`
+///
+/// Only dynamic symbols can be resolved in-process; the object offset can be passed to
+/// `addr2line` to symbolize the rest.
+fn describe_frame(ip: usize) -> String {
+    #[cfg(unix)]
+    unsafe {
+        let mut info: libc::Dl_info = core::mem::zeroed();
+        if libc::dladdr(ip as *const libc::c_void, &mut info) != 0 {
+            let symbol = if info.dli_sname.is_null() {
+                std::borrow::Cow::Borrowed("???")
+            } else {
+                std::ffi::CStr::from_ptr(info.dli_sname).to_string_lossy()
+            };
+            if !info.dli_fname.is_null() {
+                let object = std::ffi::CStr::from_ptr(info.dli_fname).to_string_lossy();
+                let offset = ip.wrapping_sub(info.dli_fbase as usize);
+                return format!("0x{:x}: {} ({}+0x{:x})", ip, symbol, object, offset);
+            }
+            return format!("0x{:x}: {}", ip, symbol);
+        }
+    }
+
+    format!("0x{:x}: ???", ip)
+}
+
+fn json_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a DHAT-compatible report of all allocations that are currently live
+///
+/// Allocations are grouped by allocation site with block counts and byte totals; the file
+/// can be opened with DHAT's `dh_view.html`. When `AUTO_ALLOCATOR_LEAK_REPORT` names a path,
+/// the exit hook writes the report there as the process ends; tests can also call this
+/// directly to check for leaks at a known point.
+///
+/// Allocation sites are captured on Linux glibc; on other platforms every block is attributed
+/// to a single root site.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// let leaked = Box::leak(Box::new([0u8; 64]));
+/// let summary = auto_allocator::write_leak_report("leaks.json").unwrap();
+/// assert!(summary.bytes >= leaked.len() as u64);
+/// ```
+pub fn write_leak_report(path: impl AsRef<Path>) -> std::io::Result<LeakSummary> {
+    // Allocations made while building the report must neither be tracked nor re-enter the lock
+    let _guard = ReentrancyGuard::enter();
+    write_report_untracked(path.as_ref())
+}
+
+fn render_report() -> (String, LeakSummary) {
+    lock_tracker().get_or_insert_with(LeakTracker::new).render_dhat_json()
+}
+
+fn write_report_untracked(path: &Path) -> std::io::Result<LeakSummary> {
+    let (json, summary) = render_report();
+    std::fs::write(path, json)?;
+    Ok(summary)
+}
+
+/// Registers the process exit hook, called once when the allocator initializes
+///
+/// Only Unix has an exit hook; elsewhere this does nothing and reports are only written by
+/// [`write_leak_report()`].
+pub(crate) fn install_exit_hook() {
+    if EXIT_HOOK_INSTALLED
+        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+        .is_ok()
+    {
+        #[cfg(unix)]
+        unsafe {
+            libc::atexit(write_report_at_exit);
+        }
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn write_report_at_exit() {
+    let _ = std::panic::catch_unwind(|| {
+        let _guard = ReentrancyGuard::enter();
+        // std's own statics are always live at exit, so there is no clean run to stay quiet
+        // for; only write a report where one was asked for
+        let Some(path) = std::env::var_os("AUTO_ALLOCATOR_LEAK_REPORT") else {
+            return;
+        };
+        let path = std::path::PathBuf::from(path);
+        let (json, summary) = render_report();
+
+        match std::fs::write(&path, json) {
+            Ok(()) if summary.blocks > 0 => eprintln!(
+                "[INFO] Auto-allocator: leak report - {} blocks ({} bytes) still live at exit across {} sites, written to {}",
+                summary.blocks,
+                summary.bytes,
+                summary.sites,
+                path.display()
+            ),
+            Ok(()) => {}
+            Err(err) => eprintln!(
+                "[WARN] Auto-allocator: failed to write leak report to {}: {}",
+                path.display(),
+                err
+            ),
+        }
+    });
+}
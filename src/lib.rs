@@ -40,6 +40,7 @@
 //! - **Windows/macOS**: mimalloc for desktop application speed
 //! - **Android/iOS**: Platform-optimized system allocators (Scudo/libmalloc)
 //! - **Docker/Kubernetes**: Optimized for containerized deployments
+//! - **Alpine/musl**: mimalloc even on a single core, since musl's malloc is slow under contention
 //! - **Embedded Systems**: Automatic embedded-alloc for all no_std platforms (RISC-V, ARM, AVR, MSP430, Xtensa, etc.)
 //! - **WASM**: Compatible allocation for web applications
 //!
@@ -47,6 +48,15 @@
 //! ```toml
 //! auto-allocator = { version = "*", features = ["secure"] }
 //! ```
+//!
+//! ## Going Further
+//!
+//! Workload hints, low-memory and glibc tuning, adaptive selection, the selection cache, huge
+//! pages, security profiles, compile-time forced backends and the opt-in diagnostic features
+//! (leak reports, allocation counting, fault injection, memory budgets, pressure monitoring,
+//! guard pages, quarantine, canaries, locked memory, tracing) are described in the README.
+//! The `AUTO_ALLOCATOR_BACKEND` variable (`system`, `mimalloc`, `mimalloc-secure`,
+//! `debug-guard`) replaces automatic selection on Unix.
 
 #![cfg_attr(target_os = "none", no_std)]
 
@@ -58,8 +68,99 @@ mod runtime;
 mod logging;
 mod system;
 mod api;
+mod profile;
+mod reason;
+#[cfg(not(target_os = "none"))]
+mod backend;
+#[cfg(not(target_os = "none"))]
+mod workload;
+#[cfg(not(target_os = "none"))]
+mod lowmem;
+#[cfg(not(target_os = "none"))]
+mod debugging;
+#[cfg(all(
+    any(
+        feature = "leak-report",
+        feature = "count-allocations",
+        feature = "forbid-allocations",
+        feature = "fault-injection",
+        feature = "memory-budget",
+        all(feature = "trace", unix)
+    ),
+    not(target_os = "none")
+))]
+mod reentrancy;
+#[cfg(all(
+    any(
+        feature = "forbid-allocations",
+        feature = "oom-diagnostics",
+        feature = "quarantine",
+        feature = "canary",
+        feature = "selection-cache",
+        feature = "trace"
+    ),
+    not(target_os = "none")
+))]
+mod rawlog;
+#[cfg(all(feature = "leak-report", not(target_os = "none")))]
+mod leak;
+#[cfg(all(feature = "count-allocations", not(target_os = "none")))]
+mod counting;
+#[cfg(all(feature = "forbid-allocations", not(target_os = "none")))]
+mod forbid;
+#[cfg(all(feature = "fault-injection", not(target_os = "none")))]
+mod fault;
+#[cfg(all(feature = "memory-budget", not(target_os = "none")))]
+mod budget;
+#[cfg(all(feature = "memory-pressure", not(target_os = "none")))]
+mod pressure;
+#[cfg(all(feature = "oom-diagnostics", not(target_os = "none")))]
+mod oom;
+#[cfg(all(any(feature = "debug-guard", feature = "security-profiles"), unix))]
+mod guard;
+#[cfg(all(feature = "quarantine", not(target_os = "none")))]
+mod quarantine;
+#[cfg(all(feature = "canary", not(target_os = "none")))]
+mod canary;
+#[cfg(any(
+    all(feature = "zero-on-free", not(target_os = "none")),
+    all(feature = "locked-memory", unix),
+    all(feature = "security-profiles", not(target_os = "none"))
+))]
+mod wipe;
+#[cfg(all(feature = "locked-memory", unix))]
+mod locked;
+#[cfg(all(feature = "huge-pages", not(target_os = "none")))]
+mod hugepage;
+#[cfg(all(feature = "adaptive", unix))]
+mod adaptive;
+#[cfg(all(feature = "selection-cache", unix))]
+mod cache;
+#[cfg(all(feature = "trace", unix))]
+mod trace;
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+mod glibc;
 
-pub use types::{AllocatorInfo, AllocatorType, SystemInfo};
+pub use types::{
+    AllocatorInfo,
+    AllocatorType,
+    DebuggingTool,
+    GlibcTuning,
+    LibcFlavor,
+    NumaNode,
+    SecurityFeatures,
+    SecurityProfile,
+    SystemInfo,
+    TransparentHugePages,
+};
+#[cfg(not(target_os = "none"))]
+pub use types::{BackendTiming, Calibration};
+#[cfg(not(target_os = "none"))]
+pub use backend::BackendAllocator;
+#[cfg(not(target_os = "none"))]
+pub use types::Workload;
+#[cfg(not(target_os = "none"))]
+pub use workload::set_workload;
 pub use format::format_memory_size;
 pub use api::{
     get_allocator_info,
@@ -69,3 +170,66 @@ pub use api::{
 };
 #[cfg(target_arch = "wasm32")]
 pub use api::wasm_auto_init;
+#[cfg(all(feature = "leak-report", not(target_os = "none")))]
+pub use leak::{write_leak_report, LeakSummary};
+#[cfg(all(feature = "count-allocations", not(target_os = "none")))]
+pub use counting::{count_allocations, AllocCounts};
+#[cfg(all(feature = "forbid-allocations", not(target_os = "none")))]
+pub use forbid::{
+    deny_allocations,
+    forbid_allocations,
+    forbidden_allocation_action,
+    forbidden_allocation_count,
+    set_forbidden_allocation_action,
+    DenyAllocationsGuard,
+    ForbiddenAllocationAction,
+};
+#[cfg(all(feature = "fault-injection", not(target_os = "none")))]
+pub use fault::{
+    inject_failures,
+    injected_failure_count,
+    set_failure_schedule,
+    with_injected_failures,
+    FailureSchedule,
+    FaultInjectionGuard,
+};
+#[cfg(all(feature = "memory-budget", not(target_os = "none")))]
+pub use budget::{
+    clear_memory_budget,
+    memory_budget_status,
+    on_soft_limit,
+    set_memory_budget,
+    BudgetStatus,
+    MemoryBudget,
+};
+#[cfg(all(feature = "memory-pressure", not(target_os = "none")))]
+pub use pressure::{
+    on_memory_pressure,
+    purge_allocator_caches,
+    start_pressure_monitor,
+    PressureEvent,
+    PressureLevel,
+    PressureMonitor,
+    PressureMonitorConfig,
+    PressureSource,
+};
+#[cfg(all(feature = "oom-diagnostics", not(target_os = "none")))]
+pub use oom::allocation_failure_count;
+#[cfg(all(feature = "oom-diagnostics", unix))]
+pub use oom::set_oom_diagnostics_fd;
+#[cfg(all(feature = "security-profiles", not(target_os = "none")))]
+pub use profile::FREE_POISON;
+#[cfg(all(feature = "debug-guard", unix))]
+pub use guard::{guard_mode, GuardMode};
+#[cfg(all(feature = "quarantine", not(target_os = "none")))]
+pub use quarantine::{
+    flush_quarantine, quarantine_stats, set_quarantine_size, QuarantineStats, QUARANTINE_POISON,
+};
+#[cfg(all(feature = "canary", not(target_os = "none")))]
+pub use canary::{verify_heap, CanaryRegion, HeapCheck, HeapCorruption};
+#[cfg(all(feature = "locked-memory", unix))]
+pub use locked::{locked_memory_status, LockedAllocator, LockedMemoryStatus};
+#[cfg(all(feature = "selection-cache", unix))]
+pub use cache::{invalidate_selection_cache, selection_cache_status, CacheState, SelectionCacheStatus};
+#[cfg(all(feature = "trace", unix))]
+pub use trace::{replay_trace, start_trace, stop_trace, ReplayReport, TraceSummary};
@@ -0,0 +1,137 @@
+use core::alloc::Layout;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicU64, Ordering};
+use allocator_api2::alloc::{AllocError, Allocator};
+use crate::platform::get_page_size_safe;
+use crate::wipe::secure_wipe;
+// ========== Locked Memory Allocator ==========
+
+static LOCKED_BYTES: AtomicU64 = AtomicU64::new(0);
+static LOCKED_ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+/// Allocator for secrets that must never reach swap or a core dump
+///
+/// Works alongside the global allocator rather than replacing it: only collections created
+/// with it (through [`allocator_api2`]) get the protection. Each allocation gets its own pages,
+/// which are:
+///
+/// - **Locked** in RAM with `mlock`, so they are never written to swap
+/// - **Excluded from core dumps** with `madvise(MADV_DONTDUMP)` on Linux
+/// - **Wiped** with volatile writes before they are unlocked and unmapped
+///
+/// Locked memory is limited by `RLIMIT_MEMLOCK` (often 64 KiB to 8 MiB for unprivileged
+/// processes). When the kernel refuses to lock more pages the allocation fails rather than
+/// silently handing out swappable memory; check [`locked_memory_status()`] for headroom.
+/// Since every allocation is rounded up to whole pages, it suits keys and passwords, not
+/// bulk data.
+///
+/// # Example
+///
+/// ```rust
+/// use allocator_api2::vec::Vec;
+/// use auto_allocator::LockedAllocator;
+///
+/// let mut key: Vec<u8, LockedAllocator> = Vec::new_in(LockedAllocator);
+/// key.extend_from_slice(b"correct horse battery staple");
+/// assert!(auto_allocator::locked_memory_status().locked_bytes > 0);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LockedAllocator;
+
+/// Locked memory held by [`LockedAllocator`] compared with the process limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockedMemoryStatus {
+    /// Bytes currently locked by `LockedAllocator` (whole pages)
+    pub locked_bytes: u64,
+
+    /// Number of live `LockedAllocator` allocations
+    pub allocations: u64,
+
+    /// Soft `RLIMIT_MEMLOCK` of the process, `None` when unlimited
+    ///
+    /// The limit also covers memory locked by other code in the process.
+    pub limit_bytes: Option<u64>,
+}
+
+impl LockedMemoryStatus {
+    /// Bytes that can still be locked before reaching the limit, `None` when unlimited
+    pub fn headroom_bytes(&self) -> Option<u64> {
+        self.limit_bytes.map(|limit| limit.saturating_sub(self.locked_bytes))
+    }
+}
+
+/// Returns locked memory usage of [`LockedAllocator`] against `RLIMIT_MEMLOCK`
+pub fn locked_memory_status() -> LockedMemoryStatus {
+    let mut limit: libc::rlimit = unsafe { core::mem::zeroed() };
+    let limit_bytes = match unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) } {
+        0 if limit.rlim_cur != libc::RLIM_INFINITY => Some(limit.rlim_cur as u64),
+        _ => None,
+    };
+
+    LockedMemoryStatus {
+        locked_bytes: LOCKED_BYTES.load(Ordering::Relaxed),
+        allocations: LOCKED_ALLOCATIONS.load(Ordering::Relaxed),
+        limit_bytes,
+    }
+}
+
+/// Length of the private mapping backing `layout`
+fn mapping_len(layout: Layout) -> Option<usize> {
+    let page = get_page_size_safe();
+    Some(layout.size().checked_add(page - 1)? & !(page - 1))
+}
+
+unsafe impl Allocator for LockedAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.size() == 0 {
+            // Zero-sized requests never touch the kernel
+            let dangling = NonNull::new(layout.align() as *mut u8).ok_or(AllocError)?;
+            return Ok(NonNull::slice_from_raw_parts(dangling, 0));
+        }
+        if layout.align() > get_page_size_safe() {
+            return Err(AllocError);
+        }
+        let len = mapping_len(layout).ok_or(AllocError)?;
+
+        unsafe {
+            let base = libc::mmap(
+                core::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            );
+            if base == libc::MAP_FAILED {
+                return Err(AllocError);
+            }
+            if libc::mlock(base, len) != 0 {
+                libc::munmap(base, len);
+                return Err(AllocError);
+            }
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            libc::madvise(base, len, libc::MADV_DONTDUMP);
+
+            LOCKED_BYTES.fetch_add(len as u64, Ordering::Relaxed);
+            LOCKED_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            let base = NonNull::new_unchecked(base as *mut u8);
+            Ok(NonNull::slice_from_raw_parts(base, len))
+        }
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if layout.size() == 0 {
+            return;
+        }
+        // Any size between the request and the returned length maps to the same pages
+        let len = mapping_len(layout).unwrap_unchecked();
+        let base = ptr.as_ptr();
+
+        secure_wipe(base, len);
+        libc::munlock(base as *const libc::c_void, len);
+        libc::munmap(base as *mut libc::c_void, len);
+
+        LOCKED_BYTES.fetch_sub(len as u64, Ordering::Relaxed);
+        LOCKED_ALLOCATIONS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
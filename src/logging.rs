@@ -1,4 +1,3 @@
-#[cfg(unix)] use libc;
 use crate::platform::LOG_FLUSHED;
 #[cfg(not(target_os = "none"))] use core::sync::atomic::Ordering;
 #[cfg(not(target_os = "none"))] use once_cell::sync::Lazy;
@@ -14,8 +13,8 @@ static PENDING_LOG_MESSAGE: Lazy<std::sync::Mutex<Option<String>>> =
 /// Immediately outputs to stderr (safe during global allocator init) and 
 /// saves for later output through the logging framework when available.
 #[cfg(not(target_os = "none"))]
-pub(crate) fn record_allocator_selection(allocator_name: &str, reason: &str) {
-    let message = format!("Auto-allocator: {} selected - {}", allocator_name, reason);
+pub(crate) fn record_allocator_selection(reason: &str) {
+    let message = format!("Auto-allocator: {}", reason);
 
     // Immediate output to stderr (only safe method in global allocator)
     #[cfg(unix)]
@@ -0,0 +1,152 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+use crate::format::format_memory_size;
+use crate::system::get_effective_memory_limit;
+// ========== Low-Memory Hosts ==========
+
+/// Below this much effective memory the system allocator is selected
+const DEFAULT_LOW_MEMORY: u64 = 1 << 30;
+
+/// Below this much effective memory mimalloc runs without eager commit and with small arenas
+const DEFAULT_CONSTRAINED_MEMORY: u64 = 4 << 30;
+
+/// Arena reservation for mimalloc on constrained hosts, in KiB as mimalloc expects
+/// (the default is 1 GiB on 64-bit)
+#[cfg(all(
+    any(feature = "_mimalloc", feature = "_mimalloc_secure"),
+    not(target_arch = "wasm32"),
+    not(debug_assertions)
+))]
+const CONSTRAINED_ARENA_RESERVE_KIB: core::ffi::c_long = 64 * 1024;
+
+/// How much memory the host leaves this process, relative to the thresholds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MemoryClass {
+    /// Enough memory for the regular selection and mimalloc defaults
+    Ample,
+    /// Regular selection, mimalloc tuned to commit lazily from small arenas
+    Constrained,
+    /// System allocator, mimalloc's reservations cost more than they return
+    Low,
+}
+
+/// Class consulted by selection, 0 until selection ran
+static APPLIED: AtomicU8 = AtomicU8::new(0);
+
+const fn encode(class: MemoryClass) -> u8 {
+    match class {
+        MemoryClass::Ample => 1,
+        MemoryClass::Constrained => 2,
+        MemoryClass::Low => 3,
+    }
+}
+
+/// Parses `<digits>[K|M|G][B]` (case-insensitive, powers of 1024) without allocating
+fn parse_size(value: &[u8]) -> Option<u64> {
+    let digits = value.iter().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    let number = value[..digits]
+        .iter()
+        .try_fold(0u64, |acc, &b| acc.checked_mul(10)?.checked_add((b - b'0') as u64))?;
+    const UNITS: [(&[u8], u32); 8] = [
+        (b"", 0), (b"B", 0), (b"K", 10), (b"KB", 10), (b"M", 20), (b"MB", 20), (b"G", 30), (b"GB", 30),
+    ];
+    let unit = &value[digits..];
+    let (_, shift) = UNITS.iter().find(|(name, _)| unit.eq_ignore_ascii_case(name))?;
+    number.checked_mul(1 << shift)
+}
+
+/// Reads a size threshold from the environment, ignoring malformed values
+fn threshold_from_env(name: &core::ffi::CStr, default: u64) -> u64 {
+    #[cfg(unix)]
+    {
+        crate::platform::with_env_var(name, parse_size).flatten().unwrap_or(default)
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = name;
+        default
+    }
+}
+
+/// Low and constrained thresholds in bytes, from `AUTO_ALLOCATOR_LOW_MEMORY` and
+/// `AUTO_ALLOCATOR_CONSTRAINED_MEMORY` when set; 0 turns a rule off
+pub(crate) fn thresholds() -> (u64, u64) {
+    (
+        threshold_from_env(c"AUTO_ALLOCATOR_LOW_MEMORY", DEFAULT_LOW_MEMORY),
+        threshold_from_env(c"AUTO_ALLOCATOR_CONSTRAINED_MEMORY", DEFAULT_CONSTRAINED_MEMORY),
+    )
+}
+
+/// Classifies an effective memory size against the thresholds
+pub(crate) fn classify(effective_memory: u64) -> MemoryClass {
+    let (low, constrained) = thresholds();
+    if effective_memory < low {
+        MemoryClass::Low
+    } else if effective_memory < constrained {
+        MemoryClass::Constrained
+    } else {
+        MemoryClass::Ample
+    }
+}
+
+/// Classifies this host and records the class as the one selection used
+pub(crate) fn apply() -> MemoryClass {
+    let class = classify(get_effective_memory_limit());
+    APPLIED.store(encode(class), Ordering::Release);
+    class
+}
+
+/// Class that shaped the selection, `Ample` when selection never consulted it
+#[cfg(all(
+    any(feature = "_mimalloc", feature = "_mimalloc_secure"),
+    not(target_arch = "wasm32"),
+    not(debug_assertions)
+))]
+fn applied_class() -> MemoryClass {
+    match APPLIED.load(Ordering::Acquire) {
+        2 => MemoryClass::Constrained,
+        3 => MemoryClass::Low,
+        _ => MemoryClass::Ample,
+    }
+}
+
+/// Explains a memory-based choice, e.g. `512MB effective memory, below the 1GB low-memory threshold`
+pub(crate) fn describe(class: MemoryClass, effective_memory: u64) -> String {
+    let (low, constrained) = thresholds();
+    let (kind, threshold) = match class {
+        MemoryClass::Low => ("low-memory", low),
+        _ => ("constrained-memory", constrained),
+    };
+    format!(
+        "{} effective memory, below the {} {} threshold",
+        format_memory_size(effective_memory),
+        format_memory_size(threshold),
+        kind
+    )
+}
+
+/// Makes mimalloc commit lazily from small arenas on constrained hosts, called once right
+/// after selection
+pub(crate) fn configure_backend(allocator_id: u8) {
+    #[cfg(all(
+        any(feature = "_mimalloc", feature = "_mimalloc_secure"),
+        not(target_arch = "wasm32"),
+        not(debug_assertions)
+    ))]
+    if matches!(allocator_id, 2 | 5) && applied_class() != MemoryClass::Ample {
+        // libmimalloc-sys only exports the stable option numbers; these come from mimalloc.h
+        const MI_OPTION_EAGER_COMMIT: libmimalloc_sys::mi_option_t = 3;
+        const MI_OPTION_ARENA_EAGER_COMMIT: libmimalloc_sys::mi_option_t = 4;
+        const MI_OPTION_ARENA_RESERVE: libmimalloc_sys::mi_option_t = 23;
+        unsafe {
+            libmimalloc_sys::mi_option_set(MI_OPTION_EAGER_COMMIT, 0);
+            libmimalloc_sys::mi_option_set(MI_OPTION_ARENA_EAGER_COMMIT, 0);
+            libmimalloc_sys::mi_option_set(MI_OPTION_ARENA_RESERVE, CONSTRAINED_ARENA_RESERVE_KIB);
+        }
+    }
+
+    let _ = allocator_id;
+}
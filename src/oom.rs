@@ -0,0 +1,152 @@
+use core::alloc::Layout;
+use core::fmt::Write as _;
+use core::sync::atomic::{AtomicU64, Ordering};
+#[cfg(unix)] use core::sync::atomic::AtomicI32;
+use std::sync::OnceLock;
+use std::time::Instant;
+use crate::platform::{backend_name, get_cpu_cores_safe};
+use crate::rawlog::StackBuffer;
+use crate::system::{get_cgroup_memory_limit, get_cgroup_memory_usage, get_total_memory_safe};
+// ========== Out-of-memory Diagnostics ==========
+
+/// Descriptor the diagnostic record is written to (stderr unless redirected)
+#[cfg(unix)]
+static DIAGNOSTICS_FD: AtomicI32 = AtomicI32::new(2);
+
+/// Minimum time between two records; failures in between are only counted
+const REPORT_INTERVAL_NS: u64 = 1_000_000_000;
+
+/// Failed allocations since the process started, reported or not
+static FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// When the last record was written, in nanoseconds since `CLOCK_BASE` plus one; 0 for never
+static LAST_REPORT_NS: AtomicU64 = AtomicU64::new(0);
+
+static CLOCK_BASE: OnceLock<Instant> = OnceLock::new();
+
+/// Redirects out-of-memory diagnostic records to an already open file descriptor
+///
+/// The descriptor must stay open for the life of the process. Opening it up front matters:
+/// once memory is exhausted, opening a log file may itself fail.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::os::unix::io::IntoRawFd;
+///
+/// let file = std::fs::File::create("/var/log/myservice-oom.jsonl").unwrap();
+/// auto_allocator::set_oom_diagnostics_fd(file.into_raw_fd());
+/// ```
+#[cfg(unix)]
+pub fn set_oom_diagnostics_fd(fd: std::os::unix::io::RawFd) {
+    DIAGNOSTICS_FD.store(fd, Ordering::Relaxed);
+}
+
+/// Returns how many allocations failed so far, including those whose record was suppressed
+///
+/// # Example
+///
+/// ```rust
+/// let mut huge: Vec<u8> = Vec::new();
+/// let before = auto_allocator::allocation_failure_count();
+/// assert!(huge.try_reserve_exact(1 << 62).is_err());
+/// assert_eq!(auto_allocator::allocation_failure_count(), before + 1);
+/// ```
+pub fn allocation_failure_count() -> u64 {
+    FAILURES.load(Ordering::Relaxed)
+}
+
+/// Why an allocation returned null
+#[derive(Clone, Copy)]
+pub(crate) enum FailureCause {
+    /// The selected backend could not provide the memory
+    Backend,
+
+    /// The memory budget's hard limit rejected the allocation before it reached the backend
+    #[cfg(feature = "memory-budget")]
+    BudgetHardLimit,
+}
+
+/// Writes a JSON line describing a failed allocation, called from `RuntimeAllocator::alloc`
+/// when the backend returns null or the memory budget rejects the request
+///
+/// A failed allocation is not necessarily fatal: `try_reserve` and other fallible APIs hand
+/// the failure back to the caller, so the record only states what failed. Code that keeps
+/// retrying a fallible allocation would flood the output, so at most one record is written per
+/// second; `failures` in the next record counts every failure, suppressed or not. It runs with
+/// the heap possibly exhausted, so it is formatted into a stack buffer and written with a raw
+/// `write`.
+#[cold]
+#[inline(never)]
+pub(crate) fn report_allocation_failure(layout: Layout, allocator_id: u8, cause: FailureCause) {
+    let failures = FAILURES.fetch_add(1, Ordering::Relaxed) + 1;
+    if !claim_report_slot() {
+        return;
+    }
+
+    let mut record = StackBuffer::<1024>::new();
+    let _ = write!(
+        record,
+        "{{\"event\":\"allocation_failed\",\"cause\":\"{}\",\"size\":{},\"align\":{},\"backend\":\"{}\",\"failures\":{}",
+        match cause {
+            FailureCause::Backend => "backend",
+            #[cfg(feature = "memory-budget")]
+            FailureCause::BudgetHardLimit => "budget_hard_limit",
+        },
+        layout.size(),
+        layout.align(),
+        backend_name(allocator_id),
+        failures
+    );
+
+    #[cfg(feature = "memory-budget")]
+    {
+        let status = crate::budget::memory_budget_status();
+        let _ = write!(
+            record,
+            ",\"live_bytes\":{},\"peak_bytes\":{},\"hard_limit_bytes\":{}",
+            status.live_bytes, status.peak_bytes, status.hard_limit_bytes
+        );
+    }
+
+    let _ = match (get_cgroup_memory_limit(), get_cgroup_memory_usage()) {
+        (Some(limit), Some(usage)) => write!(
+            record,
+            ",\"cgroup_limit_bytes\":{},\"cgroup_usage_bytes\":{}",
+            limit, usage
+        ),
+        (Some(limit), None) => write!(record, ",\"cgroup_limit_bytes\":{}", limit),
+        (None, Some(usage)) => write!(record, ",\"cgroup_usage_bytes\":{}", usage),
+        (None, None) => Ok(()),
+    };
+
+    // SystemInfo fields, gathered without the String allocations of collect_system_info()
+    let _ = writeln!(
+        record,
+        ",\"os_type\":\"{}\",\"target_arch\":\"{}\",\"cpu_cores\":{},\"total_memory_bytes\":{},\"is_debug\":{},\"is_wasm\":{}}}",
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        get_cpu_cores_safe(),
+        get_total_memory_safe(),
+        cfg!(debug_assertions),
+        cfg!(target_arch = "wasm32")
+    );
+
+    #[cfg(unix)]
+    crate::rawlog::write_fd(DIAGNOSTICS_FD.load(Ordering::Relaxed), record.as_bytes());
+
+    #[cfg(not(unix))]
+    crate::rawlog::write_stderr(record.as_bytes());
+}
+
+/// Whether this failure may write a record, i.e. the last one is at least an interval old
+fn claim_report_slot() -> bool {
+    let now = CLOCK_BASE.get_or_init(Instant::now).elapsed().as_nanos() as u64 + 1;
+    let last = LAST_REPORT_NS.load(Ordering::Relaxed);
+    if last != 0 && now.saturating_sub(last) < REPORT_INTERVAL_NS {
+        return false;
+    }
+    LAST_REPORT_NS
+        .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+        .is_ok()
+}
@@ -1,4 +1,7 @@
 use core::sync::atomic::{AtomicU8, AtomicBool};
+#[cfg(not(target_os = "none"))]
+use crate::system::get_numa_node_count_safe;
+use crate::reason::{record, SelectionReason};
 // ========== Platform Detection ==========
 
 /// Checks if the target is an embedded platform requiring specialized allocation
@@ -29,6 +32,74 @@ pub(crate) const fn can_use_mimalloc_secure() -> bool {
         not(debug_assertions)
     ))
 }
+
+/// The mimalloc build compiled in and usable on this platform; it is either regular or secure
+pub(crate) const fn usable_mimalloc() -> Option<u8> {
+    if can_use_mimalloc_secure() {
+        Some(5)
+    } else if can_use_mimalloc() {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// The mimalloc build automatic selection may use, if the active security profile accepts it
+pub(crate) fn selectable_mimalloc() -> Option<u8> {
+    usable_mimalloc().filter(|&allocator_id| crate::profile::allows_mimalloc(allocator_id))
+}
+
+/// Checks if the guard-page debugging backend can be used on this platform
+pub(crate) const fn can_use_debug_guard() -> bool {
+    cfg!(all(feature = "debug-guard", unix))
+}
+
+/// Returns the backend fixed by a `force-*` feature, which replaces every runtime rule
+///
+/// `force-system` wins over `force-mimalloc-secure`, which wins over `force-mimalloc`. A forced
+/// mimalloc that is not usable (debug builds, WASM, unsupported platforms) leaves the system
+/// allocator in place; embedded targets always keep embedded-alloc.
+pub(crate) const fn get_forced_allocator() -> Option<u8> {
+    if is_embedded_target() {
+        return None;
+    }
+
+    if cfg!(feature = "force-system") {
+        return Some(1);
+    }
+
+    if cfg!(feature = "force-mimalloc-secure") {
+        return Some(if can_use_mimalloc_secure() { 5 } else { 1 });
+    }
+
+    if cfg!(feature = "force-mimalloc") {
+        // Enabled together with `secure`, the one mimalloc build is the secure one
+        return Some(if can_use_mimalloc_secure() {
+            5
+        } else if can_use_mimalloc() {
+            2
+        } else {
+            1
+        });
+    }
+
+    None
+}
+
+/// Name of the `force-*` feature in effect, empty without one
+#[cfg(not(target_os = "none"))]
+pub(crate) const fn forced_feature() -> &'static str {
+    if cfg!(feature = "force-system") {
+        "force-system"
+    } else if cfg!(feature = "force-mimalloc-secure") {
+        "force-mimalloc-secure"
+    } else if cfg!(feature = "force-mimalloc") {
+        "force-mimalloc"
+    } else {
+        ""
+    }
+}
+
 /// This optimization avoids unnecessary runtime checks for 90% of platforms.
 pub(crate) const fn get_compile_time_allocator() -> Option<u8> {
     if is_embedded_target() {
@@ -65,26 +136,152 @@ pub(crate) const fn get_compile_time_allocator() -> Option<u8> {
 
 /// Selects allocator using compile-time rules and runtime hardware detection
 pub(crate) fn select_allocator_by_hardware() -> u8 {
-    if let Some(allocator_id) = get_compile_time_allocator() {
+    // Sanitizers, Valgrind and Miri only see heap blocks that come from the system allocator
+    #[cfg(not(target_os = "none"))]
+    let debugging_tool = crate::debugging::detect().is_some();
+    #[cfg(target_os = "none")]
+    let debugging_tool = false;
+
+    if let Some((allocator_id, reason)) = fixed_allocator(debugging_tool) {
+        record(reason);
         return allocator_id;
     }
 
-    // Only high-performance platforms reach here - need CPU core detection
-    // Use zero-allocation CPU detection to avoid infinite recursion
-    let cpu_cores = get_cpu_cores_safe();
+    // A decision persisted by an earlier run replaces runtime detection while still valid
+    #[cfg(all(feature = "selection-cache", unix))]
+    return crate::cache::cached_selection(select_allocator_at_runtime);
+
+    #[cfg(not(all(feature = "selection-cache", unix)))]
+    select_allocator_at_runtime()
+}
+
+/// Returns the allocator fixed before any runtime rule applies, with the rule that fixed it
+pub(crate) fn fixed_allocator(debugging_tool: bool) -> Option<(u8, SelectionReason)> {
+    // A forced backend is baked into the allocation path, so nothing may select another one
+    if let Some(allocator_id) = get_forced_allocator() {
+        return Some((allocator_id, SelectionReason::Forced));
+    }
+
+    // An explicit override beats every automatic rule
+    if let Some(allocator_id) = get_allocator_override() {
+        return Some((allocator_id, SelectionReason::Override));
+    }
+
+    if debugging_tool {
+        return Some((1, SelectionReason::DebuggingTool));
+    }
+
+    get_compile_time_allocator().map(|allocator_id| (allocator_id, SelectionReason::CompileTime))
+}
+
+/// Chooses among the runtime candidates on platforms without a compile-time choice
+#[cfg(not(target_os = "none"))]
+fn select_allocator_at_runtime() -> u8 {
+    let memory = crate::system::get_effective_memory_limit();
+    let inputs = SelectionInputs {
+        // Classified even when a hint decides, since constrained hosts still get a tuned mimalloc
+        memory_class: crate::lowmem::apply(),
+        memory,
+        cpu_cores: get_effective_cpu_cores_safe(),
+        numa_nodes: get_numa_node_count_safe(),
+        musl: is_musl(),
+        workload: crate::workload::apply_hint(),
+        usable_mimalloc: usable_mimalloc(),
+        selectable_mimalloc: selectable_mimalloc(),
+    };
+
+    #[cfg(all(feature = "adaptive", unix))]
+    let calibrate = crate::adaptive::select;
+    #[cfg(not(all(feature = "adaptive", unix)))]
+    let calibrate = || None;
+
+    let (allocator_id, reason) = decide(&inputs, calibrate);
+    record(reason);
+    allocator_id
+}
+
+/// Embedded targets always have a compile-time choice
+#[cfg(target_os = "none")]
+fn select_allocator_at_runtime() -> u8 {
+    record(SelectionReason::Hardware);
+    1
+}
+
+/// What the runtime selection rules weigh, gathered by the caller
+#[cfg(not(target_os = "none"))]
+pub(crate) struct SelectionInputs {
+    /// Class of the effective memory
+    pub(crate) memory_class: crate::lowmem::MemoryClass,
+    /// Memory the process may use, in bytes
+    pub(crate) memory: u64,
+    /// Cores the process may use
+    pub(crate) cpu_cores: usize,
+    /// Online NUMA nodes
+    pub(crate) numa_nodes: usize,
+    /// Whether the system allocator is musl's malloc
+    pub(crate) musl: bool,
+    /// Declared workload hint
+    pub(crate) workload: Option<crate::types::Workload>,
+    /// The mimalloc build compiled in, see [`usable_mimalloc`]
+    pub(crate) usable_mimalloc: Option<u8>,
+    /// The mimalloc build the security profile accepts, see [`selectable_mimalloc`]
+    pub(crate) selectable_mimalloc: Option<u8>,
+}
+
+/// Applies the runtime selection rules, returning the allocator ID and the rule that chose it
+///
+/// `calibrate` is only called when no hint or low-memory rule decides, and returns the
+/// calibration winner, if any.
+#[cfg(not(target_os = "none"))]
+pub(crate) fn decide(inputs: &SelectionInputs, calibrate: impl FnOnce() -> Option<u8>) -> (u8, SelectionReason) {
+    use crate::lowmem::MemoryClass;
+
+    // A declared workload replaces the core-count rule and the calibration
+    if let Some(workload) = inputs.workload {
+        let allocator_id =
+            crate::workload::recommend(workload, inputs.cpu_cores, inputs.memory, inputs.selectable_mimalloc);
+        return (allocator_id, SelectionReason::Workload(workload));
+    }
+
+    // With little memory, mimalloc's reservations cost more than its speed returns
+    if inputs.memory_class == MemoryClass::Low {
+        return (1, SelectionReason::LowMemory);
+    }
 
-    // Multi-core systems: prefer mimalloc (secure > regular > system)
-    if cpu_cores >= 2 && can_use_mimalloc_secure() {
-        return 5; // mimalloc-secure
+    // Opt-in calibration measures the candidates instead of trusting the core count
+    if let Some(allocator_id) = calibrate() {
+        return (allocator_id, SelectionReason::Calibrated);
     }
 
-    // Check if mimalloc is available
-    // Since build script ensures compatibility, mimalloc is available if feature is enabled
-    if cpu_cores >= 2 && can_use_mimalloc() {
-        return 2; // mimalloc
+    // Multi-core or multi-node systems prefer mimalloc, and musl's malloc is slow enough that
+    // mimalloc pays off even on a single core
+    let prefer_mimalloc = inputs.cpu_cores >= 2 || inputs.numa_nodes >= 2 || inputs.musl;
+
+    // Since build script ensures compatibility, mimalloc is available if feature is enabled;
+    // the security profile decides whether its build (regular or secure) may be used
+    if prefer_mimalloc {
+        if let Some(allocator_id) = inputs.selectable_mimalloc {
+            let reason = if inputs.musl {
+                SelectionReason::Musl
+            } else if inputs.memory_class == MemoryClass::Constrained {
+                SelectionReason::ConstrainedMemory
+            } else {
+                SelectionReason::Hardware
+            };
+            return (allocator_id, reason);
+        }
+        if inputs.usable_mimalloc.is_some() {
+            return (1, SelectionReason::ProfileExcludesMimalloc);
+        }
     }
 
-    1 // system (single-core or all high-performance allocators unavailable)
+    (1, SelectionReason::Hardware) // system (single-core or mimalloc unavailable)
+}
+
+/// Whether the system allocator is musl's malloc
+#[cfg(not(target_os = "none"))]
+pub(crate) fn is_musl() -> bool {
+    crate::system::get_libc_flavor() == crate::types::LibcFlavor::Musl
 }
 
 /// Get CPU core count without allocating memory (to avoid infinite recursion)
@@ -120,11 +317,100 @@ pub(crate) fn get_cpu_cores_safe() -> usize {
     }
 }
 
+/// Get the cores this process may use without allocating memory
+///
+/// On Linux the online count is narrowed by the affinity mask and the cgroup CPU quota.
+#[cfg(not(target_os = "none"))]
+pub(crate) fn get_effective_cpu_cores_safe() -> usize {
+    #[cfg(target_os = "linux")]
+    {
+        crate::system::get_effective_cpu_cores()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        get_cpu_cores_safe()
+    }
+}
+
+/// Get the memory page size without allocating memory
+#[cfg(all(any(feature = "debug-guard", feature = "security-profiles", feature = "locked-memory"), unix))]
+pub(crate) fn get_page_size_safe() -> usize {
+    static PAGE_SIZE: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+    let cached = PAGE_SIZE.load(core::sync::atomic::Ordering::Relaxed);
+    if cached != 0 {
+        return cached;
+    }
+    let page = match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as usize,
+        _ => 4096,
+    };
+    PAGE_SIZE.store(page, core::sync::atomic::Ordering::Relaxed);
+    page
+}
+
+// ========== Allocator Override ==========
+
+/// Environment variable naming a backend that replaces the automatic selection
+pub(crate) const BACKEND_OVERRIDE_VAR: &str = "AUTO_ALLOCATOR_BACKEND";
+
+/// Runs `f` on the raw bytes of an environment variable without allocating
+///
+/// Safe to call from inside the global allocator, where `std::env` would recurse.
+#[cfg(unix)]
+pub(crate) fn with_env_var<R>(name: &core::ffi::CStr, f: impl FnOnce(&[u8]) -> R) -> Option<R> {
+    unsafe {
+        let value = libc::getenv(name.as_ptr());
+        if value.is_null() {
+            None
+        } else {
+            Some(f(core::ffi::CStr::from_ptr(value).to_bytes()))
+        }
+    }
+}
+
+/// Returns the allocator requested through `AUTO_ALLOCATOR_BACKEND`, if it is usable here
+///
+/// Accepted values are `system`, `mimalloc`, `mimalloc-secure` and `debug-guard`. Backends
+/// that are not compiled in or not supported on this platform are ignored, so automatic
+/// selection applies instead.
+pub(crate) fn get_allocator_override() -> Option<u8> {
+    #[cfg(unix)]
+    {
+        with_env_var(c"AUTO_ALLOCATOR_BACKEND", |value| match value {
+            b"system" => Some(1),
+            b"mimalloc" if can_use_mimalloc() => Some(2),
+            b"mimalloc-secure" if can_use_mimalloc_secure() => Some(5),
+            b"debug-guard" if can_use_debug_guard() => Some(6),
+            _ => None,
+        })
+        .flatten()
+    }
+
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Short backend name used in logs and diagnostics
+#[cfg(not(target_os = "none"))]
+pub(crate) const fn backend_name(allocator_id: u8) -> &'static str {
+    match allocator_id {
+        6 => "debug-guard",
+        5 => "mimalloc-secure",
+        2 => "mimalloc",
+        4 => "embedded-alloc",
+        _ => "system",
+    }
+}
+
 // ========== Embedded Heap Configuration ==========
 // ========== Runtime Allocator Selection ==========
 
 // Global state for allocator selection and logging  
-// ID mapping: 0=uninitialized, 1=system, 2=mimalloc, 3=jemalloc, 4=embedded, 5=mimalloc-secure
+// ID mapping: 0=uninitialized, 1=system, 2=mimalloc, 3=jemalloc, 4=embedded, 5=mimalloc-secure, 6=debug-guard
 pub(crate) static RUNTIME_ALLOCATOR_ID: AtomicU8 = AtomicU8::new(0);
 #[cfg(not(target_os = "none"))]
 pub(crate) static ALLOCATOR_LOGGED: AtomicBool = AtomicBool::new(false);
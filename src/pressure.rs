@@ -0,0 +1,401 @@
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+use crate::runtime::RuntimeAllocator;
+use crate::system::{get_available_memory, get_cgroup_memory_limit, get_cgroup_memory_usage, get_total_memory_safe};
+// ========== Memory Pressure Notifications ==========
+
+/// Severity of a memory pressure event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PressureLevel {
+    /// Tasks are stalling on memory or usage is high; a good time to shed caches
+    Moderate,
+
+    /// All tasks are stalling, the cgroup hit its limit, or usage is close to exhaustion
+    Critical,
+}
+
+/// Where a memory pressure event was detected
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressureSource {
+    /// Linux Pressure Stall Information trigger (`memory.pressure` or `/proc/pressure/memory`)
+    Psi,
+
+    /// cgroup v2 `memory.events` counters (`high`, `max`, `oom`, `oom_kill`)
+    CgroupEvents,
+
+    /// Periodic usage check, used where PSI is unavailable
+    Poller,
+}
+
+/// A memory pressure notification delivered to handlers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PressureEvent {
+    /// Severity of the event
+    pub level: PressureLevel,
+
+    /// Detection mechanism that raised the event
+    pub source: PressureSource,
+
+    /// Memory still available to the process when the event was raised, if known
+    pub available_bytes: Option<u64>,
+}
+
+/// Settings for [`start_pressure_monitor()`]
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+///
+/// let config = auto_allocator::PressureMonitorConfig {
+///     poll_interval: Duration::from_millis(500),
+///     ..Default::default()
+/// };
+/// let _monitor = auto_allocator::start_pressure_monitor(config).unwrap();
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PressureMonitorConfig {
+    /// Stall time of *some* tasks per window that raises a moderate PSI event
+    pub moderate_stall: Duration,
+
+    /// Stall time of *all* tasks per window that raises a critical PSI event
+    pub critical_stall: Duration,
+
+    /// PSI tracking window; unprivileged processes need a multiple of 2 seconds
+    pub window: Duration,
+
+    /// Interval of the fallback poller, also the longest time stopping the monitor can take
+    pub poll_interval: Duration,
+
+    /// Memory usage percentage at which the poller raises a moderate event
+    pub moderate_usage_percent: u8,
+
+    /// Memory usage percentage at which the poller raises a critical event
+    pub critical_usage_percent: u8,
+
+    /// Purge the active allocator's caches on critical events
+    pub purge_on_critical: bool,
+}
+
+impl Default for PressureMonitorConfig {
+    fn default() -> Self {
+        Self {
+            moderate_stall: Duration::from_millis(150),
+            critical_stall: Duration::from_millis(100),
+            window: Duration::from_secs(2),
+            poll_interval: Duration::from_secs(1),
+            moderate_usage_percent: 80,
+            critical_usage_percent: 95,
+            purge_on_critical: true,
+        }
+    }
+}
+
+type PressureHandler = Box<dyn Fn(&PressureEvent) + Send + Sync>;
+
+static HANDLERS: Mutex<Vec<PressureHandler>> = Mutex::new(Vec::new());
+
+/// Registers a handler called on every memory pressure event
+///
+/// Handlers run on the monitor thread, not inside the allocator, so they can freely lock,
+/// allocate and free. Handlers registered before or after the monitor starts are both served.
+pub fn on_memory_pressure<F>(handler: F)
+where
+    F: Fn(&PressureEvent) + Send + Sync + 'static,
+{
+    HANDLERS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .push(Box::new(handler));
+}
+
+/// Returns cached free memory of the active allocator to the operating system
+///
+/// Drains the use-after-free quarantine first when the `quarantine` feature is enabled, then
+/// calls `mi_collect` for mimalloc and `malloc_trim` for the glibc system allocator; backends
+/// without such an interface only get the quarantined blocks back.
+pub fn purge_allocator_caches() {
+    RuntimeAllocator::backend_purge();
+}
+
+/// Handle to a running pressure monitor; dropping it stops the monitor thread
+#[must_use = "the pressure monitor stops when its handle is dropped"]
+pub struct PressureMonitor {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PressureMonitor {
+    /// Stops the monitor and waits for its thread to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for PressureMonitor {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Starts a background thread that watches for memory pressure
+///
+/// On Linux it arms PSI triggers (preferring the cgroup's `memory.pressure` over the
+/// system-wide `/proc/pressure/memory`) and watches cgroup v2 `memory.events`. Where PSI is
+/// unavailable, a poller compares memory usage (cgroup usage against its limit, or system
+/// usage against physical memory) with the configured thresholds every `poll_interval`.
+pub fn start_pressure_monitor(config: PressureMonitorConfig) -> io::Result<PressureMonitor> {
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let stop = Arc::clone(&stop);
+        std::thread::Builder::new()
+            .name("auto-allocator-pressure".into())
+            .spawn(move || run_monitor(config, &stop))?
+    };
+    Ok(PressureMonitor { stop, thread: Some(thread) })
+}
+
+fn dispatch(config: &PressureMonitorConfig, level: PressureLevel, source: PressureSource) {
+    let event = PressureEvent { level, source, available_bytes: get_available_memory() };
+    if level == PressureLevel::Critical && config.purge_on_critical {
+        purge_allocator_caches();
+    }
+    let handlers = HANDLERS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for handler in handlers.iter() {
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| handler(&event)));
+    }
+}
+
+/// Current memory usage as a percentage of what the process may use
+fn usage_percent() -> Option<u64> {
+    if let (Some(limit), Some(usage)) = (get_cgroup_memory_limit(), get_cgroup_memory_usage()) {
+        return Some(usage.saturating_mul(100) / limit.max(1));
+    }
+    let total = get_total_memory_safe();
+    let available = get_available_memory()?;
+    Some(total.saturating_sub(available).saturating_mul(100) / total.max(1))
+}
+
+/// Edge-triggered usage poller: reports each rise into a higher level once
+struct Poller {
+    last: Option<PressureLevel>,
+}
+
+impl Poller {
+    fn check(&mut self, config: &PressureMonitorConfig) {
+        let Some(percent) = usage_percent() else {
+            return;
+        };
+        let level = if percent >= config.critical_usage_percent as u64 {
+            Some(PressureLevel::Critical)
+        } else if percent >= config.moderate_usage_percent as u64 {
+            Some(PressureLevel::Moderate)
+        } else {
+            None
+        };
+        if let Some(current) = level {
+            if !matches!(self.last, Some(last) if last >= current) {
+                dispatch(config, current, PressureSource::Poller);
+            }
+        }
+        self.last = level;
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn run_monitor(config: PressureMonitorConfig, stop: &AtomicBool) {
+    let mut poller = Poller { last: None };
+    while !stop.load(Ordering::Relaxed) {
+        poller.check(&config);
+        std::thread::sleep(config.poll_interval);
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn run_monitor(config: PressureMonitorConfig, stop: &AtomicBool) {
+    use std::os::unix::io::AsRawFd;
+
+    let mut sources = linux::PressureSources::open(&config);
+    let mut poller = Poller { last: None };
+    let timeout_ms = config.poll_interval.as_millis().min(i32::MAX as u128) as i32;
+
+    while !stop.load(Ordering::Relaxed) {
+        let mut fds: Vec<libc::pollfd> = sources
+            .files()
+            .map(|file| libc::pollfd { fd: file.as_raw_fd(), events: libc::POLLPRI, revents: 0 })
+            .collect();
+
+        if fds.is_empty() {
+            poller.check(&config);
+            std::thread::sleep(config.poll_interval);
+            continue;
+        }
+
+        let ready = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout_ms) };
+        if ready < 0 {
+            if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            break;
+        }
+        if ready == 0 {
+            if !sources.has_psi() {
+                poller.check(&config);
+            }
+            continue;
+        }
+
+        for (index, fd) in fds.iter().enumerate().rev() {
+            if fd.revents == 0 {
+                continue;
+            }
+            for (level, source) in sources.handle_ready(index, fd.revents) {
+                dispatch(&config, level, source);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::fs::{File, OpenOptions};
+    use std::ffi::OsStr;
+    use std::io::{Read, Seek, SeekFrom, Write};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::{Path, PathBuf};
+    use crate::system::{cgroup_file_path, CgroupHierarchy, CGROUP_PATH_MAX};
+    use super::{PressureLevel, PressureMonitorConfig, PressureSource};
+
+    /// cgroup v2 `memory.events` counters that signal pressure
+    #[derive(Default, Clone, Copy)]
+    struct CgroupEventCounters {
+        high: u64,
+        max: u64,
+        oom: u64,
+        oom_kill: u64,
+    }
+
+    enum Source {
+        Psi { file: File, level: PressureLevel },
+        CgroupEvents { file: File, counters: CgroupEventCounters },
+    }
+
+    pub(super) struct PressureSources {
+        sources: Vec<Source>,
+    }
+
+    impl PressureSources {
+        pub(super) fn open(config: &PressureMonitorConfig) -> Self {
+            let mut sources = Vec::new();
+
+            let psi_path = cgroup_paths(b"memory.pressure")
+                .chain(std::iter::once(PathBuf::from("/proc/pressure/memory")))
+                .find(|path| path.exists());
+            if let Some(path) = psi_path {
+                let triggers = [
+                    ("some", config.moderate_stall, PressureLevel::Moderate),
+                    ("full", config.critical_stall, PressureLevel::Critical),
+                ];
+                for (kind, stall, level) in triggers {
+                    if let Some(file) = arm_psi_trigger(&path, kind, stall, config) {
+                        sources.push(Source::Psi { file, level });
+                    }
+                }
+            }
+
+            if let Some(mut file) = cgroup_paths(b"memory.events").find_map(|path| File::open(path).ok()) {
+                if let Some(counters) = read_event_counters(&mut file) {
+                    sources.push(Source::CgroupEvents { file, counters });
+                }
+            }
+
+            Self { sources }
+        }
+
+        pub(super) fn files(&self) -> impl Iterator<Item = &File> {
+            self.sources.iter().map(|source| match source {
+                Source::Psi { file, .. } | Source::CgroupEvents { file, .. } => file,
+            })
+        }
+
+        pub(super) fn has_psi(&self) -> bool {
+            self.sources.iter().any(|source| matches!(source, Source::Psi { .. }))
+        }
+
+        /// Processes a ready descriptor, returning the events it produced
+        pub(super) fn handle_ready(&mut self, index: usize, revents: libc::c_short) -> Vec<(PressureLevel, PressureSource)> {
+            let mut events = Vec::new();
+            match &mut self.sources[index] {
+                Source::Psi { level, .. } => {
+                    if revents & libc::POLLERR != 0 {
+                        // The trigger was torn down (e.g. cgroup removed); stop watching it
+                        self.sources.remove(index);
+                    } else if revents & libc::POLLPRI != 0 {
+                        events.push((*level, PressureSource::Psi));
+                    }
+                }
+                Source::CgroupEvents { file, counters } => {
+                    if let Some(current) = read_event_counters(file) {
+                        if current.max > counters.max || current.oom > counters.oom || current.oom_kill > counters.oom_kill {
+                            events.push((PressureLevel::Critical, PressureSource::CgroupEvents));
+                        } else if current.high > counters.high {
+                            events.push((PressureLevel::Moderate, PressureSource::CgroupEvents));
+                        }
+                        *counters = current;
+                    }
+                }
+            }
+            events
+        }
+    }
+
+    /// Opens a PSI file and registers a `<some|full> <stall us> <window us>` trigger on it
+    /// Paths of a cgroup v2 file, this process's own cgroup first, then the root
+    fn cgroup_paths(file: &'static [u8]) -> impl Iterator<Item = PathBuf> {
+        [true, false].into_iter().filter_map(move |own| {
+            let mut buf = [0u8; CGROUP_PATH_MAX];
+            let path = cgroup_file_path(CgroupHierarchy::Unified, file, own, &mut buf)?;
+            Some(PathBuf::from(OsStr::from_bytes(path.to_bytes())))
+        })
+    }
+
+    fn arm_psi_trigger(path: &Path, kind: &str, stall: std::time::Duration, config: &PressureMonitorConfig) -> Option<File> {
+        let mut file = OpenOptions::new().read(true).write(true).open(path).ok()?;
+        let trigger = format!("{} {} {}\0", kind, stall.as_micros(), config.window.as_micros());
+        file.write_all(trigger.as_bytes()).ok()?;
+        Some(file)
+    }
+
+    fn read_event_counters(file: &mut File) -> Option<CgroupEventCounters> {
+        let mut text = String::new();
+        file.seek(SeekFrom::Start(0)).ok()?;
+        file.read_to_string(&mut text).ok()?;
+
+        let mut counters = CgroupEventCounters::default();
+        for line in text.lines() {
+            let mut parts = line.split_whitespace();
+            let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let value = value.parse().unwrap_or(0);
+            match key {
+                "high" => counters.high = value,
+                "max" => counters.max = value,
+                "oom" => counters.oom = value,
+                "oom_kill" => counters.oom_kill = value,
+                _ => {}
+            }
+        }
+        Some(counters)
+    }
+}
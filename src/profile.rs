@@ -0,0 +1,170 @@
+use crate::types::{SecurityFeatures, SecurityProfile};
+#[cfg(all(feature = "security-profiles", not(target_os = "none")))]
+use core::alloc::Layout;
+#[cfg(all(feature = "security-profiles", not(target_os = "none")))]
+use core::sync::atomic::{AtomicU8, Ordering};
+// ========== Security Profiles ==========
+
+/// Byte written over freed blocks by the hardened profile
+///
+/// Reads through a dangling pointer return this pattern instead of the old contents.
+#[cfg(all(feature = "security-profiles", not(target_os = "none")))]
+pub const FREE_POISON: u8 = 0xFD;
+
+/// Allocations at least this large end at a guard page under the hardened profiles
+///
+/// Matches glibc's default mmap threshold: blocks this size are usually mapped on their own
+/// anyway, so the extra page costs little.
+#[cfg(all(feature = "security-profiles", unix))]
+pub(crate) const LARGE_ALLOCATION: usize = 128 * 1024;
+
+/// Profile selected by the `profile-*` features, or implied by `secure`
+const fn compile_time_profile() -> SecurityProfile {
+    if cfg!(feature = "profile-paranoid") {
+        SecurityProfile::Paranoid
+    } else if cfg!(feature = "profile-hardened") {
+        SecurityProfile::Hardened
+    } else if cfg!(any(feature = "profile-balanced", feature = "_mimalloc_secure")) {
+        SecurityProfile::Balanced
+    } else {
+        SecurityProfile::Performance
+    }
+}
+
+// 0=unresolved, otherwise the profile's position plus one
+#[cfg(all(feature = "security-profiles", not(target_os = "none")))]
+static PROFILE: AtomicU8 = AtomicU8::new(0);
+
+/// Returns the profile requested through `AUTO_ALLOCATOR_PROFILE`, if any
+///
+/// Accepted values are `performance`, `balanced`, `hardened` and `paranoid`.
+#[cfg(all(feature = "security-profiles", not(target_os = "none")))]
+fn get_profile_override() -> Option<SecurityProfile> {
+    #[cfg(unix)]
+    {
+        crate::platform::with_env_var(c"AUTO_ALLOCATOR_PROFILE", |value| match value {
+            b"performance" => Some(SecurityProfile::Performance),
+            b"balanced" => Some(SecurityProfile::Balanced),
+            b"hardened" => Some(SecurityProfile::Hardened),
+            b"paranoid" => Some(SecurityProfile::Paranoid),
+            _ => None,
+        })
+        .flatten()
+    }
+
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// Returns the security profile in effect
+///
+/// Resolved on the first allocation and fixed afterwards, so blocks are always freed the same
+/// way they were allocated.
+#[inline]
+pub(crate) fn active_profile() -> SecurityProfile {
+    #[cfg(all(feature = "security-profiles", not(target_os = "none")))]
+    {
+        if PROFILE.load(Ordering::Relaxed) == 0 {
+            let profile = get_profile_override().unwrap_or(compile_time_profile());
+            let _ = PROFILE.compare_exchange(0, profile as u8 + 1, Ordering::Relaxed, Ordering::Relaxed);
+        }
+
+        match PROFILE.load(Ordering::Relaxed) {
+            4 => SecurityProfile::Paranoid,
+            3 => SecurityProfile::Hardened,
+            2 => SecurityProfile::Balanced,
+            _ => SecurityProfile::Performance,
+        }
+    }
+
+    #[cfg(not(all(feature = "security-profiles", not(target_os = "none"))))]
+    {
+        compile_time_profile()
+    }
+}
+
+/// Name of a profile as used in `AUTO_ALLOCATOR_PROFILE` and selection reasons
+#[cfg(not(target_os = "none"))]
+pub(crate) const fn profile_name(profile: SecurityProfile) -> &'static str {
+    match profile {
+        SecurityProfile::Performance => "performance",
+        SecurityProfile::Balanced => "balanced",
+        SecurityProfile::Hardened => "hardened",
+        SecurityProfile::Paranoid => "paranoid",
+    }
+}
+
+/// Whether automatic selection may use the given mimalloc build under the active profile
+///
+/// mimalloc is compiled either regular or secure. `performance` asks for no hardening and the
+/// stricter profiles ask for secure mimalloc, so a build that contradicts the profile is
+/// passed over in favour of the system allocator.
+pub(crate) fn allows_mimalloc(allocator_id: u8) -> bool {
+    match allocator_id {
+        5 => active_profile() >= SecurityProfile::Balanced,
+        2 => active_profile() == SecurityProfile::Performance,
+        _ => true,
+    }
+}
+
+/// Whether freed blocks currently go through the quarantine, which poisons them
+fn quarantine_active() -> bool {
+    #[cfg(all(feature = "quarantine", not(target_os = "none")))]
+    {
+        crate::quarantine::quarantine_stats().max_bytes > 0
+    }
+
+    #[cfg(not(all(feature = "quarantine", not(target_os = "none"))))]
+    {
+        false
+    }
+}
+
+/// Maps a profile onto the hardening actually in effect on the given backend
+///
+/// Freed blocks are scrubbed in a fixed order (zero-on-free wipe, profile scrub, quarantine
+/// poison), so only the last step is reported: a block that ends up poisoned is not zeroed.
+pub(crate) fn security_features(profile: SecurityProfile, allocator_id: u8) -> SecurityFeatures {
+    // Poisoning, wiping and guard pages are done by this crate and need the runtime support
+    let runtime = cfg!(all(feature = "security-profiles", not(target_os = "none")));
+
+    let free_poisoning = (runtime && profile == SecurityProfile::Hardened) || quarantine_active();
+    let zero_on_free = !free_poisoning
+        && ((runtime && profile == SecurityProfile::Paranoid)
+            || cfg!(all(feature = "zero-on-free", not(target_os = "none"))));
+
+    SecurityFeatures {
+        secure_mimalloc: allocator_id == 5,
+        free_poisoning,
+        zero_on_free,
+        large_guard_pages: allocator_id == 6
+            || (runtime && cfg!(unix) && profile >= SecurityProfile::Hardened),
+        // Secure mimalloc extends its free lists in random order; no other backend is relied on
+        randomized_allocation: allocator_id == 5,
+    }
+}
+
+/// Whether `layout` gets its own mapping with a trailing guard page
+#[cfg(all(feature = "security-profiles", unix))]
+#[inline]
+pub(crate) fn guards_large(layout: Layout) -> bool {
+    layout.size() >= LARGE_ALLOCATION
+        && layout.align() <= crate::platform::get_page_size_safe()
+        && active_profile() >= SecurityProfile::Hardened
+}
+
+/// Poisons or wipes a block being freed, as the active profile requires
+#[cfg(all(feature = "security-profiles", not(target_os = "none")))]
+#[inline]
+pub(crate) unsafe fn scrub_freed(ptr: *mut u8, layout: Layout) {
+    match active_profile() {
+        SecurityProfile::Hardened => crate::wipe::secure_fill(ptr, layout.size(), FREE_POISON),
+        // Already wiped when the zero-on-free feature is enabled
+        SecurityProfile::Paranoid if !cfg!(feature = "zero-on-free") => {
+            crate::wipe::secure_wipe(ptr, layout.size())
+        }
+        _ => {}
+    }
+}
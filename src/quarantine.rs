@@ -0,0 +1,181 @@
+use core::alloc::Layout;
+use core::fmt::Write as _;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use crate::rawlog::{write_stderr, StackBuffer};
+use crate::runtime::RuntimeAllocator;
+// ========== Use-after-free Quarantine ==========
+
+/// Byte written over every quarantined block
+///
+/// A use-after-free read returns this pattern instead of stale data, and any other value
+/// found when the block leaves quarantine means something wrote through a dangling pointer.
+pub const QUARANTINE_POISON: u8 = 0xDF;
+
+/// Maximum number of blocks held at once, independent of the byte limit
+const MAX_BLOCKS: usize = 4096;
+
+/// Default byte limit of the quarantine
+const DEFAULT_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, Copy)]
+struct Block {
+    addr: usize,
+    size: usize,
+    align: usize,
+}
+
+impl Block {
+    const EMPTY: Block = Block { addr: 0, size: 0, align: 1 };
+
+    fn layout(&self) -> Layout {
+        // Built from a valid `Layout` in `release`, so it is valid again here
+        unsafe { Layout::from_size_align_unchecked(self.size, self.align) }
+    }
+}
+
+/// FIFO of freed blocks, oldest first
+struct Ring {
+    blocks: [Block; MAX_BLOCKS],
+    head: usize,
+    len: usize,
+    bytes: usize,
+}
+
+impl Ring {
+    const fn new() -> Self {
+        Self { blocks: [Block::EMPTY; MAX_BLOCKS], head: 0, len: 0, bytes: 0 }
+    }
+
+    fn fits(&self, size: usize, max_bytes: usize) -> bool {
+        self.len < MAX_BLOCKS && self.bytes + size <= max_bytes
+    }
+
+    fn push(&mut self, block: Block) {
+        self.blocks[(self.head + self.len) % MAX_BLOCKS] = block;
+        self.len += 1;
+        self.bytes += block.size;
+    }
+
+    fn pop_oldest(&mut self) -> Option<Block> {
+        if self.len == 0 {
+            return None;
+        }
+        let block = self.blocks[self.head];
+        self.head = (self.head + 1) % MAX_BLOCKS;
+        self.len -= 1;
+        self.bytes -= block.size;
+        Some(block)
+    }
+}
+
+static RING: Mutex<Ring> = Mutex::new(Ring::new());
+static MAX_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_BYTES);
+static RELEASED_BLOCKS: AtomicU64 = AtomicU64::new(0);
+
+/// Snapshot of the use-after-free quarantine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuarantineStats {
+    /// Blocks currently held back from the backend
+    pub blocks: usize,
+
+    /// Bytes currently held back from the backend
+    pub bytes: usize,
+
+    /// Configured byte limit
+    pub max_bytes: usize,
+
+    /// Blocks that passed verification and were returned to the backend
+    pub released_blocks: u64,
+}
+
+fn ring() -> MutexGuard<'static, Ring> {
+    RING.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Sets how many bytes of freed memory the quarantine may hold (16 MiB by default)
+///
+/// Larger quarantines catch dangling pointers that are used long after the free, at the cost
+/// of memory. Blocks bigger than the limit skip the quarantine, and `0` disables it. Shrinking
+/// the limit takes effect as blocks are freed; call [`flush_quarantine()`] to apply it at once.
+pub fn set_quarantine_size(max_bytes: usize) {
+    MAX_BYTES.store(max_bytes, Ordering::Relaxed);
+}
+
+/// Returns the current state of the quarantine
+pub fn quarantine_stats() -> QuarantineStats {
+    let ring = ring();
+    QuarantineStats {
+        blocks: ring.len,
+        bytes: ring.bytes,
+        max_bytes: MAX_BYTES.load(Ordering::Relaxed),
+        released_blocks: RELEASED_BLOCKS.load(Ordering::Relaxed),
+    }
+}
+
+/// Verifies every quarantined block and returns them all to the backend
+///
+/// Useful at the end of a test or before a memory-hungry phase. Aborts the process with a
+/// report on stderr if any block was written after it was freed.
+pub fn flush_quarantine() {
+    loop {
+        let oldest = ring().pop_oldest();
+        match oldest {
+            Some(block) => unsafe { verify_and_release(block) },
+            None => return,
+        }
+    }
+}
+
+/// Poisons a freed block and parks it in the quarantine, called from
+/// `RuntimeAllocator::dealloc` in place of the backend's own deallocation
+///
+/// Blocks pushed out to make room are verified and released to the backend.
+pub(crate) unsafe fn release(ptr: *mut u8, layout: Layout) {
+    let max_bytes = MAX_BYTES.load(Ordering::Relaxed);
+    if layout.size() > max_bytes {
+        return RuntimeAllocator::release_block(ptr, layout);
+    }
+
+    core::ptr::write_bytes(ptr, QUARANTINE_POISON, layout.size());
+    let incoming = Block { addr: ptr as usize, size: layout.size(), align: layout.align() };
+
+    // Evict one block at a time so the backend is never called with the lock held
+    loop {
+        let evicted = {
+            let mut ring = ring();
+            if ring.fits(incoming.size, max_bytes) {
+                ring.push(incoming);
+                return;
+            }
+            ring.pop_oldest()
+        };
+        match evicted {
+            Some(block) => verify_and_release(block),
+            // Limit shrank below this block while it was being poisoned
+            None => return RuntimeAllocator::release_block(ptr, layout),
+        }
+    }
+}
+
+unsafe fn verify_and_release(block: Block) {
+    let bytes = core::slice::from_raw_parts(block.addr as *const u8, block.size);
+    if let Some(offset) = bytes.iter().position(|&byte| byte != QUARANTINE_POISON) {
+        report_corruption(block, offset, bytes[offset]);
+    }
+    RELEASED_BLOCKS.fetch_add(1, Ordering::Relaxed);
+    RuntimeAllocator::release_block(block.addr as *mut u8, block.layout());
+}
+
+#[cold]
+fn report_corruption(block: Block, offset: usize, found: u8) -> ! {
+    let mut message = StackBuffer::<256>::new();
+    let _ = writeln!(
+        message,
+        "[FATAL] Auto-allocator: use-after-free write detected in freed block at {:#x} \
+         (size {}, align {}): byte {} is {:#04x}, expected {:#04x}",
+        block.addr, block.size, block.align, offset, found, QUARANTINE_POISON
+    );
+    write_stderr(message.as_bytes());
+    std::process::abort();
+}
@@ -0,0 +1,98 @@
+// ========== Allocation-free Diagnostics Output ==========
+
+/// Fixed-size formatting buffer for messages emitted from inside the global allocator
+///
+/// Output beyond the capacity is silently truncated, so formatting never fails or allocates.
+#[cfg(any(
+    feature = "forbid-allocations",
+    feature = "oom-diagnostics",
+    feature = "quarantine",
+    feature = "canary",
+    all(feature = "selection-cache", unix)
+))]
+pub(crate) struct StackBuffer<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+#[cfg(any(
+    feature = "forbid-allocations",
+    feature = "oom-diagnostics",
+    feature = "quarantine",
+    feature = "canary",
+    all(feature = "selection-cache", unix)
+))]
+impl<const N: usize> StackBuffer<N> {
+    pub(crate) const fn new() -> Self {
+        Self { buf: [0; N], len: 0 }
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+#[cfg(any(
+    feature = "forbid-allocations",
+    feature = "oom-diagnostics",
+    feature = "quarantine",
+    feature = "canary",
+    all(feature = "selection-cache", unix)
+))]
+impl<const N: usize> core::fmt::Write for StackBuffer<N> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let available = N - self.len;
+        let take = s.len().min(available);
+        self.buf[self.len..self.len + take].copy_from_slice(&s.as_bytes()[..take]);
+        self.len += take;
+        Ok(())
+    }
+}
+
+/// Writes bytes straight to stderr without going through buffered or locking std I/O
+#[cfg(any(
+    feature = "forbid-allocations",
+    feature = "quarantine",
+    feature = "canary",
+    all(feature = "trace", unix),
+    all(feature = "oom-diagnostics", not(unix))
+))]
+pub(crate) fn write_stderr(bytes: &[u8]) {
+    #[cfg(unix)]
+    write_fd(2, bytes);
+
+    #[cfg(not(unix))]
+    {
+        use std::io::Write;
+        let _ = std::io::stderr().write_all(bytes);
+    }
+}
+
+/// Writes bytes to a raw file descriptor, retrying short writes
+#[cfg(unix)]
+pub(crate) fn write_fd(fd: i32, mut bytes: &[u8]) {
+    while !bytes.is_empty() {
+        let written = unsafe { libc::write(fd, bytes.as_ptr() as *const libc::c_void, bytes.len()) };
+        if written <= 0 {
+            return;
+        }
+        bytes = &bytes[written as usize..];
+    }
+}
+
+/// Writes the calling thread's backtrace to stderr, one symbolized frame per line
+///
+/// Uses glibc's `backtrace_symbols_fd`, which formats straight into the descriptor. Elsewhere
+/// only a note is written, since symbolizing through std would allocate.
+#[cfg(feature = "canary")]
+pub(crate) fn write_backtrace() {
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    unsafe {
+        let mut frames = [core::ptr::null_mut::<libc::c_void>(); 32];
+        let depth = libc::backtrace(frames.as_mut_ptr(), frames.len() as libc::c_int);
+        libc::backtrace_symbols_fd(frames.as_ptr(), depth, 2);
+    }
+
+    #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+    write_stderr(b"  (backtrace unavailable on this platform)\n");
+}
@@ -0,0 +1,189 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+#[cfg(not(target_os = "none"))]
+use crate::format::{format_memory_size, format_numa_nodes};
+#[cfg(not(target_os = "none"))]
+use crate::platform::{backend_name, forced_feature, usable_mimalloc, BACKEND_OVERRIDE_VAR};
+#[cfg(not(target_os = "none"))]
+use crate::types::{SystemInfo, Workload};
+// ========== Selection Reasons ==========
+
+/// Rule that decided which allocator serves the process
+///
+/// Selection records one at the moment it decides, and [`describe`] renders it for both the
+/// startup log and `AllocatorInfo::reason`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SelectionReason {
+    /// Fixed by a `force-*` feature
+    Forced,
+    /// Named by `AUTO_ALLOCATOR_BACKEND`
+    Override,
+    /// A sanitizer, Valgrind or Miri only tracks system allocations
+    DebuggingTool,
+    /// Decided by the target and build profile (embedded, WASM, debug builds, native allocators)
+    CompileTime,
+    /// Reused from the persisted selection cache
+    Cached,
+    /// Recommended for the declared workload hint
+    #[cfg(not(target_os = "none"))]
+    Workload(Workload),
+    /// System allocator below the low-memory threshold
+    LowMemory,
+    /// Hardware favours mimalloc, but the security profile rules out the compiled build
+    ProfileExcludesMimalloc,
+    /// Fastest backend in the adaptive calibration
+    Calibrated,
+    /// mimalloc over musl's malloc, which is slow under contention
+    Musl,
+    /// mimalloc tuned for a host below the constrained-memory threshold
+    ConstrainedMemory,
+    /// Core and NUMA node count: mimalloc when multi-threaded, the system allocator otherwise
+    Hardware,
+}
+
+/// Reason recorded by selection, 0 until selection ran
+static RECORDED: AtomicU8 = AtomicU8::new(0);
+
+// The workload itself is kept by `workload::apply_hint`, which selection always calls first
+const fn encode(reason: SelectionReason) -> u8 {
+    match reason {
+        SelectionReason::Forced => 1,
+        SelectionReason::Override => 2,
+        SelectionReason::DebuggingTool => 3,
+        SelectionReason::CompileTime => 4,
+        SelectionReason::Cached => 5,
+        #[cfg(not(target_os = "none"))]
+        SelectionReason::Workload(_) => 6,
+        SelectionReason::LowMemory => 7,
+        SelectionReason::ProfileExcludesMimalloc => 8,
+        SelectionReason::Calibrated => 9,
+        SelectionReason::Musl => 10,
+        SelectionReason::ConstrainedMemory => 11,
+        SelectionReason::Hardware => 12,
+    }
+}
+
+/// Records why selection chose its allocator; allocation-free, so safe inside the allocator
+pub(crate) fn record(reason: SelectionReason) {
+    RECORDED.store(encode(reason), Ordering::Release);
+}
+
+/// Reason recorded by selection, `Hardware` if selection has not run
+#[cfg(not(target_os = "none"))]
+pub(crate) fn recorded() -> SelectionReason {
+    match RECORDED.load(Ordering::Acquire) {
+        1 => SelectionReason::Forced,
+        2 => SelectionReason::Override,
+        3 => SelectionReason::DebuggingTool,
+        4 => SelectionReason::CompileTime,
+        5 => SelectionReason::Cached,
+        6 => match crate::workload::applied_hint() {
+            Some(workload) => SelectionReason::Workload(workload),
+            None => SelectionReason::Hardware,
+        },
+        7 => SelectionReason::LowMemory,
+        8 => SelectionReason::ProfileExcludesMimalloc,
+        9 => SelectionReason::Calibrated,
+        10 => SelectionReason::Musl,
+        11 => SelectionReason::ConstrainedMemory,
+        _ => SelectionReason::Hardware,
+    }
+}
+
+/// Renders a selection reason with the hardware it was made on, e.g.
+/// `mimalloc selected by runtime hardware analysis (8 cores, 16GB total RAM)`
+#[cfg(not(target_os = "none"))]
+pub(crate) fn describe(reason: SelectionReason, allocator_id: u8, system_info: &SystemInfo) -> String {
+    let name = backend_name(allocator_id);
+    let total_memory = format_memory_size(system_info.total_memory_bytes);
+    let hardware = format!(
+        "{} cores, {} total RAM{}",
+        system_info.cpu_cores,
+        total_memory,
+        format_numa_nodes(system_info.numa_node_count)
+    );
+    let memory_class = || {
+        let class = match reason {
+            SelectionReason::LowMemory => crate::lowmem::MemoryClass::Low,
+            _ => crate::lowmem::MemoryClass::Constrained,
+        };
+        crate::lowmem::describe(class, system_info.effective_memory_bytes)
+    };
+
+    match reason {
+        SelectionReason::Forced => format!(
+            "{} forced at compile time by the {} feature ({})",
+            name,
+            forced_feature(),
+            hardware
+        ),
+        SelectionReason::Override => {
+            format!("{} selected by {} override ({})", name, BACKEND_OVERRIDE_VAR, hardware)
+        }
+        SelectionReason::DebuggingTool => format!(
+            "{} selected because the process runs under {}, which only tracks system allocations ({})",
+            name,
+            system_info.debugging_tool.map_or("a debugging tool", crate::debugging::tool_name),
+            hardware
+        ),
+        SelectionReason::CompileTime => describe_compile_time(name, system_info, &total_memory, &hardware),
+        SelectionReason::Cached => {
+            #[cfg(all(feature = "adaptive", unix))]
+            let decision = if crate::adaptive::is_calibrated() { "adaptive calibration" } else { "decision" };
+            #[cfg(not(all(feature = "adaptive", unix)))]
+            let decision = "decision";
+            format!("{} selected from cached {} ({})", name, decision, hardware)
+        }
+        SelectionReason::Workload(workload) => format!(
+            "{} selected for {} workload ({})",
+            name,
+            crate::workload::workload_name(workload),
+            hardware
+        ),
+        SelectionReason::LowMemory => {
+            format!("{} selected for low-memory host ({}, {})", name, hardware, memory_class())
+        }
+        SelectionReason::ProfileExcludesMimalloc => format!(
+            "{} selected because the {} security profile rules out {} ({})",
+            name,
+            crate::profile::profile_name(crate::profile::active_profile()),
+            usable_mimalloc().map_or("mimalloc", backend_name),
+            hardware
+        ),
+        SelectionReason::Calibrated => format!("{} selected by adaptive calibration ({})", name, hardware),
+        SelectionReason::Musl => format!(
+            "{} selected over musl malloc, which is slow under contention ({})",
+            name, hardware
+        ),
+        SelectionReason::ConstrainedMemory => format!(
+            "{} selected by runtime hardware analysis, tuned for constrained memory ({}, {})",
+            name,
+            hardware,
+            memory_class()
+        ),
+        SelectionReason::Hardware if allocator_id == 1 => format!(
+            "{} selected by runtime hardware analysis, single core or mimalloc unavailable ({})",
+            name, hardware
+        ),
+        SelectionReason::Hardware => format!("{} selected by runtime hardware analysis ({})", name, hardware),
+    }
+}
+
+/// Names the platform rule behind a choice fixed at compile time
+#[cfg(not(target_os = "none"))]
+fn describe_compile_time(name: &str, system_info: &SystemInfo, total_memory: &str, hardware: &str) -> String {
+    if system_info.is_wasm {
+        return format!("{} selected for WASM environment ({} total RAM)", name, total_memory);
+    }
+    if system_info.is_debug {
+        return format!("{} selected for debug build ({})", name, hardware);
+    }
+    let platform = match system_info.os_type.as_ref() {
+        "android" => "Android's Scudo allocator (security-first, use-after-free protection)",
+        "ios" => "iOS libmalloc (Apple-optimized, memory pressure handling)",
+        "freebsd" | "netbsd" => "the BSD native jemalloc (highly optimized, deep system integration)",
+        "openbsd" => "OpenBSD's security-hardened allocator (exploit mitigation)",
+        "solaris" | "illumos" => "Solaris libumem (NUMA-aware, enterprise grade)",
+        _ => "the platform allocator",
+    };
+    format!("{} selected to keep {} ({})", name, platform, hardware)
+}
@@ -0,0 +1,41 @@
+use core::cell::Cell;
+// ========== Allocator Reentrancy Guard ==========
+
+thread_local! {
+    static IN_ALLOCATOR_HOOK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Marks the current thread as running allocator bookkeeping code
+///
+/// Hooks in the global allocator need heap-backed collections of their own. While the
+/// guard is held, allocations made by the same thread bypass every hook and go straight
+/// to the selected backend, which prevents infinite recursion and self-deadlock.
+pub(crate) struct ReentrancyGuard;
+
+impl ReentrancyGuard {
+    /// Enters the guard, or returns `None` if this thread is already inside a hook
+    ///
+    /// Also returns `None` once thread-local storage has been torn down during thread exit.
+    #[inline]
+    #[cfg(any(feature = "leak-report", feature = "memory-budget", all(feature = "trace", unix)))]
+    pub(crate) fn enter() -> Option<Self> {
+        IN_ALLOCATOR_HOOK
+            .try_with(|active| if active.replace(true) { None } else { Some(ReentrancyGuard) })
+            .ok()
+            .flatten()
+    }
+
+    /// Returns whether the current thread is inside allocator bookkeeping code
+    #[inline]
+    #[cfg(any(feature = "count-allocations", feature = "forbid-allocations", feature = "fault-injection"))]
+    pub(crate) fn is_held() -> bool {
+        IN_ALLOCATOR_HOOK.try_with(|active| active.get()).unwrap_or(true)
+    }
+}
+
+impl Drop for ReentrancyGuard {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = IN_ALLOCATOR_HOOK.try_with(|active| active.set(false));
+    }
+}
@@ -2,10 +2,20 @@
 #[cfg(target_os = "none")] use crate::embedded::embedded_heap_config;
 use core::sync::atomic::Ordering;
 use core::alloc::{GlobalAlloc, Layout};
-use crate::platform::{RUNTIME_ALLOCATOR_ID, ALLOCATOR_LOGGED, select_allocator_by_hardware};
+use crate::platform::{RUNTIME_ALLOCATOR_ID, ALLOCATOR_LOGGED, get_forced_allocator, select_allocator_by_hardware};
+#[cfg(not(target_os = "none"))]
+use crate::reason::{describe, recorded};
+#[cfg(not(target_os = "none"))]
 use crate::system::collect_system_info;
+#[cfg(all(
+    any(feature = "_mimalloc", feature = "_mimalloc_secure"),
+    not(target_arch = "wasm32"),
+    not(debug_assertions),
+    not(target_os = "none")
+))]
+use crate::system::get_numa_node_count_safe;
+#[cfg(not(target_os = "none"))]
 use crate::logging::record_allocator_selection;
-use crate::format::format_memory_size;
 // ========== Safe Runtime Allocator Implementation ==========
 
 pub struct RuntimeAllocator;
@@ -23,21 +33,112 @@ impl RuntimeAllocator {
             // Record selection information (ensure only logged once)
             Self::log_allocator_selection(selected_id);
 
+            Self::initialize_backend(selected_id);
             selected_id
         } else {
             current_id
         }
     }
 
+    /// Stores the backend fixed by a `force-*` feature, from a static constructor
+    ///
+    /// The allocation path never reaches `get_allocator_id` when the backend is a constant, so
+    /// nothing is selected here; the choice is logged once allocator information is queried.
+    #[cfg(all(
+        any(feature = "force-system", feature = "force-mimalloc", feature = "force-mimalloc-secure"),
+        not(target_os = "none")
+    ))]
+    fn initialize_forced_backend() {
+        if let Some(allocator_id) = get_forced_allocator() {
+            crate::reason::record(crate::reason::SelectionReason::Forced);
+            RUNTIME_ALLOCATOR_ID.store(allocator_id, Ordering::Release);
+            Self::initialize_backend(allocator_id);
+        }
+    }
+
+    /// Applies backend options and starts the opt-in hooks, once the backend is known
+    #[cold]
+    fn initialize_backend(allocator_id: u8) {
+        // Backend options that must be set before its first allocation
+        Self::configure_backend(allocator_id);
+
+        // Opt-in leak report written when the process exits
+        #[cfg(all(feature = "leak-report", not(target_os = "none")))]
+        crate::leak::install_exit_hook();
+
+        // Opt-in allocation trace requested with AUTO_ALLOCATOR_TRACE
+        #[cfg(all(feature = "trace", unix))]
+        crate::trace::start_from_env();
+    }
+
+    /// ID of the backend serving allocations
+    ///
+    /// A constant with a `force-*` feature, so the dispatch folds into a direct call without
+    /// loading `RUNTIME_ALLOCATOR_ID`.
+    #[inline(always)]
+    fn active_allocator_id() -> u8 {
+        const FORCED: Option<u8> = get_forced_allocator();
+        match FORCED {
+            Some(allocator_id) => allocator_id,
+            None => Self::get_allocator_id(),
+        }
+    }
+
+    /// Applies hardware-dependent backend options, called once right after selection
+    #[cold]
+    fn configure_backend(allocator_id: u8) {
+        // Leave the system allocator exactly as a debugging tool expects it
+        #[cfg(not(target_os = "none"))]
+        if crate::debugging::detect().is_some() {
+            return;
+        }
+
+        // Node-local mimalloc arenas on multi-socket machines
+        #[cfg(all(
+            any(feature = "_mimalloc", feature = "_mimalloc_secure"),
+            not(target_arch = "wasm32"),
+            not(debug_assertions),
+            not(target_os = "none")
+        ))]
+        if matches!(allocator_id, 2 | 5) {
+            let numa_nodes = get_numa_node_count_safe();
+            if numa_nodes > 1 {
+                unsafe {
+                    libmimalloc_sys::mi_option_set(
+                        libmimalloc_sys::mi_option_use_numa_nodes,
+                        numa_nodes as core::ffi::c_long,
+                    )
+                };
+            }
+        }
+
+        #[cfg(all(feature = "huge-pages", not(target_os = "none")))]
+        crate::hugepage::configure_backend(allocator_id);
+
+        // Purge timing for footprint- or latency-sensitive workloads
+        #[cfg(not(target_os = "none"))]
+        crate::workload::configure_backend(allocator_id);
+
+        // Lazy commit and small arenas on memory-constrained hosts
+        #[cfg(not(target_os = "none"))]
+        crate::lowmem::configure_backend(allocator_id);
+
+        // Bounded arenas and fixed thresholds when falling back to glibc malloc
+        #[cfg(all(target_os = "linux", target_env = "gnu"))]
+        crate::glibc::configure_backend(allocator_id);
+
+        let _ = allocator_id;
+    }
+
     #[cold]
     #[cfg(not(target_os = "none"))]
-    fn log_allocator_selection(allocator_id: u8) {
+    pub(crate) fn log_allocator_selection(allocator_id: u8) {
         if ALLOCATOR_LOGGED
             .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
             .is_ok()
         {
-            let (name, reason) = Self::get_allocator_log_info(allocator_id);
-            record_allocator_selection(name, &reason);
+            let reason = describe(recorded(), allocator_id, &collect_system_info());
+            record_allocator_selection(&reason);
         }
     }
 
@@ -46,96 +147,6 @@ impl RuntimeAllocator {
     fn log_allocator_selection(_allocator_id: u8) {
         // No logging in no_std environments
     }
-
-    /// Get logging information based on allocator ID and compile-time platform detection
-    #[cfg(not(target_os = "none"))]
-    fn get_allocator_log_info(allocator_id: u8) -> (&'static str, String) {
-        match allocator_id {
-            5 => {
-                let system_info = collect_system_info();
-                ("mimalloc-secure", format!(
-                    "security-hardened choice - runtime detected ({} cores, {} total RAM)",
-                    system_info.cpu_cores,
-                    format_memory_size(system_info.total_memory_bytes)
-                ))
-            },
-            2 => {
-                let system_info = collect_system_info();
-                ("mimalloc", format!(
-                    "optimal performance choice - runtime detected ({} cores, {} total RAM)",
-                    system_info.cpu_cores,
-                    format_memory_size(system_info.total_memory_bytes)
-                ))
-            },
-            4 => {
-                let system_info = collect_system_info();
-                ("embedded-alloc", format!(
-                    "embedded platform - compile-time selected ({} total RAM)",
-                    format_memory_size(system_info.total_memory_bytes)
-                ))
-            },
-            _ => {
-                // System allocator - determine reason based on compile-time platform detection
-                if cfg!(debug_assertions) {
-                    let system_info = collect_system_info();
-                    ("system", format!(
-                        "debug build - compile-time selected ({} cores, {} total RAM)",
-                        system_info.cpu_cores,
-                        format_memory_size(system_info.total_memory_bytes)
-                    ))
-                } else if cfg!(target_arch = "wasm32") {
-                    let system_info = collect_system_info();
-                    ("system", format!(
-                        "WASM environment - compile-time selected ({} total RAM)",
-                        format_memory_size(system_info.total_memory_bytes)
-                    ))
-                } else if cfg!(target_os = "android") {
-                    let system_info = collect_system_info();
-                    ("system", format!(
-                        "Android Scudo allocator - compile-time selected (security-first policy) ({} cores, {} total RAM)",
-                        system_info.cpu_cores,
-                        format_memory_size(system_info.total_memory_bytes)
-                    ))
-                } else if cfg!(target_os = "ios") {
-                    let system_info = collect_system_info();
-                    ("system", format!(
-                        "iOS libmalloc allocator - compile-time selected (Apple optimized) ({} cores, {} total RAM)",
-                        system_info.cpu_cores,
-                        format_memory_size(system_info.total_memory_bytes)
-                    ))
-                } else if cfg!(any(target_os = "freebsd", target_os = "netbsd")) {
-                    let system_info = collect_system_info();
-                    ("system", format!(
-                        "BSD native jemalloc - compile-time selected (platform optimized) ({} cores, {} total RAM)",
-                        system_info.cpu_cores,
-                        format_memory_size(system_info.total_memory_bytes)
-                    ))
-                } else if cfg!(target_os = "openbsd") {
-                    let system_info = collect_system_info();
-                    ("system", format!(
-                        "OpenBSD security-hardened allocator - compile-time selected ({} cores, {} total RAM)",
-                        system_info.cpu_cores,
-                        format_memory_size(system_info.total_memory_bytes)
-                    ))
-                } else if cfg!(any(target_os = "solaris", target_os = "illumos")) {
-                    let system_info = collect_system_info();
-                    ("system", format!(
-                        "Solaris libumem allocator - compile-time selected (enterprise grade) ({} cores, {} total RAM)",
-                        system_info.cpu_cores,
-                        format_memory_size(system_info.total_memory_bytes)
-                    ))
-                } else {
-                    // High-performance platforms that fell back to system (single-core or mimalloc unavailable)
-                    let system_info = collect_system_info();
-                    ("system", format!(
-                        "runtime fallback - single-core or mimalloc unavailable ({} cores, {} total RAM)",
-                        system_info.cpu_cores,
-                        format_memory_size(system_info.total_memory_bytes)
-                    ))
-                }
-            },
-        }
-    }
 }
 
 // Branch prediction optimization
@@ -149,12 +160,29 @@ fn unlikely(b: bool) -> bool {
     b
 }
 
-// ========== Global Allocator Implementation - Platform-specific VTable handling ==========
+// ========== Backend Dispatch - Platform-specific VTable handling ==========
 
-unsafe impl GlobalAlloc for RuntimeAllocator {
+impl RuntimeAllocator {
+    /// Allocates from the selected backend without any instrumentation
     #[inline]
-    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
-        match Self::get_allocator_id() {
+    pub(crate) unsafe fn backend_alloc(layout: Layout) -> *mut u8 {
+        // Hardened profiles give large blocks their own mapping ending at a guard page
+        #[cfg(all(feature = "security-profiles", unix))]
+        if crate::profile::guards_large(layout) {
+            return crate::guard::map_guarded(layout, crate::guard::GuardMode::Overflow);
+        }
+
+        Self::alloc_with(Self::active_allocator_id(), layout)
+    }
+
+    /// Allocates from the backend with the given ID
+    #[inline]
+    pub(crate) unsafe fn alloc_with(allocator_id: u8, layout: Layout) -> *mut u8 {
+        match allocator_id {
+
+            // debug-guard - guard-page debugging backend, only selected by override
+            #[cfg(all(feature = "debug-guard", unix))]
+            6 => crate::guard::alloc(layout),
 
             // mimalloc-secure - security-hardened allocator with 10% performance overhead
             #[cfg(all(
@@ -206,9 +234,25 @@ unsafe impl GlobalAlloc for RuntimeAllocator {
         }
     }
 
+    /// Returns memory to the selected backend without any instrumentation
     #[inline]
-    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
-        match Self::get_allocator_id() {
+    pub(crate) unsafe fn backend_dealloc(ptr: *mut u8, layout: Layout) {
+        #[cfg(all(feature = "security-profiles", unix))]
+        if crate::profile::guards_large(layout) {
+            return crate::guard::unmap_guarded(ptr, layout, crate::guard::GuardMode::Overflow);
+        }
+
+        Self::dealloc_with(Self::active_allocator_id(), ptr, layout)
+    }
+
+    /// Returns memory to the backend with the given ID
+    #[inline]
+    pub(crate) unsafe fn dealloc_with(allocator_id: u8, ptr: *mut u8, layout: Layout) {
+        match allocator_id {
+
+            // debug-guard - unmaps the allocation together with its guard page
+            #[cfg(all(feature = "debug-guard", unix))]
+            6 => crate::guard::dealloc(ptr, layout),
 
             // mimalloc-secure - security-hardened allocator
             #[cfg(all(
@@ -259,7 +303,156 @@ unsafe impl GlobalAlloc for RuntimeAllocator {
     }
 }
 
+impl RuntimeAllocator {
+    /// Hands a block freed by the program back to the backend, undoing any redzone wrapping
+    #[inline]
+    pub(crate) unsafe fn release_block(ptr: *mut u8, layout: Layout) {
+        #[cfg(all(feature = "canary", not(target_os = "none")))]
+        let (ptr, layout) = crate::canary::underlying(ptr, layout);
+
+        Self::backend_dealloc(ptr, layout)
+    }
+
+    /// Returns cached free memory of the selected backend to the operating system
+    #[cfg(all(feature = "memory-pressure", not(target_os = "none")))]
+    pub(crate) fn backend_purge() {
+        // Quarantined blocks have not reached the backend yet, so it could not return them
+        #[cfg(feature = "quarantine")]
+        crate::quarantine::flush_quarantine();
+
+        match Self::get_allocator_id() {
+            #[cfg(all(
+                any(feature = "_mimalloc", feature = "_mimalloc_secure"),
+                not(target_arch = "wasm32"),
+                not(debug_assertions)
+            ))]
+            2 | 5 => unsafe { libmimalloc_sys::mi_collect(true) },
+
+            #[cfg(all(target_os = "linux", target_env = "gnu"))]
+            _ => unsafe {
+                libc::malloc_trim(0);
+            },
+
+            #[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+            _ => {}
+        }
+    }
+}
+
+// ========== Global Allocator Implementation ==========
+
+unsafe impl GlobalAlloc for RuntimeAllocator {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        #[cfg(all(feature = "forbid-allocations", not(target_os = "none")))]
+        crate::forbid::check_allocation(layout);
+
+        #[cfg(all(feature = "fault-injection", not(target_os = "none")))]
+        if crate::fault::should_fail(layout) {
+            return core::ptr::null_mut();
+        }
+
+        #[cfg(all(feature = "memory-budget", not(target_os = "none")))]
+        if !crate::budget::reserve(layout.size()) {
+            #[cfg(feature = "oom-diagnostics")]
+            crate::oom::report_allocation_failure(
+                layout,
+                Self::get_allocator_id(),
+                crate::oom::FailureCause::BudgetHardLimit,
+            );
+            return core::ptr::null_mut();
+        }
+
+        #[cfg(all(feature = "canary", not(target_os = "none")))]
+        let ptr = crate::canary::alloc(layout);
+
+        #[cfg(not(all(feature = "canary", not(target_os = "none"))))]
+        let ptr = Self::backend_alloc(layout);
+
+        #[cfg(all(feature = "memory-budget", not(target_os = "none")))]
+        if ptr.is_null() {
+            crate::budget::release(layout.size());
+        }
+
+        #[cfg(all(feature = "oom-diagnostics", not(target_os = "none")))]
+        if unlikely(ptr.is_null()) {
+            crate::oom::report_allocation_failure(layout, Self::get_allocator_id(), crate::oom::FailureCause::Backend);
+        }
+
+        #[cfg(all(feature = "huge-pages", target_os = "linux"))]
+        crate::hugepage::advise(ptr, layout.size());
+
+        #[cfg(all(feature = "count-allocations", not(target_os = "none")))]
+        crate::counting::record_alloc(ptr, layout);
+
+        #[cfg(all(feature = "leak-report", not(target_os = "none")))]
+        crate::leak::record_alloc(ptr, layout);
+
+        #[cfg(all(feature = "trace", unix))]
+        crate::trace::record_alloc(ptr, layout);
+
+        ptr
+    }
+
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        #[cfg(all(feature = "count-allocations", not(target_os = "none")))]
+        crate::counting::record_dealloc();
+
+        #[cfg(all(feature = "leak-report", not(target_os = "none")))]
+        crate::leak::record_dealloc(ptr);
+
+        #[cfg(all(feature = "trace", unix))]
+        crate::trace::record_dealloc(ptr);
+
+        #[cfg(all(feature = "memory-budget", not(target_os = "none")))]
+        crate::budget::release(layout.size());
+
+        // Also covers realloc: the default GlobalAlloc::realloc frees the old block through here
+        #[cfg(all(feature = "zero-on-free", not(target_os = "none")))]
+        crate::wipe::secure_wipe(ptr, layout.size());
+
+        #[cfg(all(feature = "security-profiles", not(target_os = "none")))]
+        crate::profile::scrub_freed(ptr, layout);
+
+        #[cfg(all(feature = "canary", not(target_os = "none")))]
+        crate::canary::release(ptr, layout);
+
+        #[cfg(all(feature = "quarantine", not(target_os = "none")))]
+        crate::quarantine::release(ptr, layout);
+
+        #[cfg(not(all(feature = "quarantine", not(target_os = "none"))))]
+        Self::release_block(ptr, layout)
+    }
+
+    /// The default reallocation, recorded in the trace as one event instead of an allocation
+    /// and a deallocation
+    #[cfg(all(feature = "trace", unix))]
+    #[inline]
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+        let new_ptr = crate::trace::as_one_realloc(|| {
+            let new_ptr = self.alloc(new_layout);
+            if !new_ptr.is_null() {
+                core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+                self.dealloc(ptr, layout);
+            }
+            new_ptr
+        });
+        crate::trace::record_realloc(ptr, new_ptr, layout, new_size);
+        new_ptr
+    }
+}
+
 #[global_allocator]
 static GLOBAL: RuntimeAllocator = RuntimeAllocator;
 
+#[cfg(all(
+    any(feature = "force-system", feature = "force-mimalloc", feature = "force-mimalloc-secure"),
+    not(target_os = "none")
+))]
+crate::__static_constructor!(INITIALIZE_FORCED_BACKEND => {
+    RuntimeAllocator::initialize_forced_backend();
+});
+
 // ========== Logging System ==========
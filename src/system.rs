@@ -1,4 +1,4 @@
-use crate::types::SystemInfo;
+use crate::types::{LibcFlavor, NumaNode, SystemInfo, TransparentHugePages};
 // ========== System Information Collection ==========
 
 #[cfg(not(target_os = "none"))]
@@ -10,9 +10,16 @@ pub(crate) fn collect_system_info() -> SystemInfo {
             .map(|n| n.get())
             .unwrap_or(1),
         total_memory_bytes: total_memory,
+        effective_memory_bytes: get_effective_memory_limit(),
         is_debug: cfg!(debug_assertions),
         is_wasm: cfg!(target_arch = "wasm32"),
         target_arch: std::env::consts::ARCH.to_string(),
+        libc_flavor: get_libc_flavor(),
+        debugging_tool: crate::debugging::detect(),
+        transparent_huge_pages: get_transparent_huge_pages(),
+        huge_page_sizes: get_huge_page_sizes(),
+        numa_node_count: get_numa_node_count_safe(),
+        numa_nodes: get_numa_nodes(),
     }
 }
 
@@ -24,6 +31,7 @@ pub(crate) fn collect_system_info() -> SystemInfo {
         os_type: "embedded",
         cpu_cores: 1, // Assume single core for embedded
         total_memory_bytes: total_memory,
+        effective_memory_bytes: total_memory,
         is_debug: cfg!(debug_assertions),
         is_wasm: false,
         target_arch: {
@@ -49,6 +57,44 @@ pub(crate) fn collect_system_info() -> SystemInfo {
             )))]
             { "unknown" }
         },
+        libc_flavor: LibcFlavor::Other,
+        debugging_tool: None,
+        transparent_huge_pages: TransparentHugePages::Unsupported,
+        huge_page_sizes: &[],
+        numa_node_count: 1,
+        numa_nodes: &[],
+    }
+}
+
+/// Detects the C library: glibc and its version at runtime, the others from the target
+///
+/// Allocation-free, so it is safe to call during global allocator initialization.
+#[cfg(not(target_os = "none"))]
+pub(crate) fn get_libc_flavor() -> LibcFlavor {
+    #[cfg(all(target_os = "linux", target_env = "gnu"))]
+    {
+        // "2.39", possibly followed by a distribution suffix
+        let version = unsafe { core::ffi::CStr::from_ptr(libc::gnu_get_libc_version()) }.to_bytes();
+        let minor_at = version.iter().position(|&b| b == b'.').map_or(version.len(), |dot| dot + 1);
+        LibcFlavor::Glibc {
+            major: parse_leading_u64(version).unwrap_or(0) as u32,
+            minor: parse_leading_u64(&version[minor_at..]).unwrap_or(0) as u32,
+        }
+    }
+
+    #[cfg(target_env = "musl")]
+    {
+        LibcFlavor::Musl
+    }
+
+    #[cfg(target_os = "android")]
+    {
+        LibcFlavor::Bionic
+    }
+
+    #[cfg(not(any(all(target_os = "linux", target_env = "gnu"), target_env = "musl", target_os = "android")))]
+    {
+        LibcFlavor::Other
     }
 }
 
@@ -57,7 +103,7 @@ pub(crate) fn collect_system_info() -> SystemInfo {
 /// Uses platform-specific APIs for servers/desktop systems and conservative defaults for embedded platforms.
 /// Critical: This function must not allocate memory as it's called during global allocator setup.
 #[allow(unreachable_code)]
-fn get_total_memory_safe() -> u64 {
+pub(crate) fn get_total_memory_safe() -> u64 {
     #[cfg(target_arch = "wasm32")]
     {
         // WASM can dynamically detect memory through core::arch::wasm32
@@ -154,3 +200,409 @@ fn get_total_memory_safe() -> u64 {
     2u64 << 30
 }
 
+// ========== Container Memory Limits ==========
+
+/// Reads a small sysfs/procfs file into `buf` without allocating, returning the bytes read
+#[cfg(any(target_os = "linux", all(feature = "selection-cache", unix)))]
+pub(crate) fn read_file_into(path: &core::ffi::CStr, buf: &mut [u8]) -> Option<usize> {
+    unsafe {
+        let fd = libc::open(path.as_ptr(), libc::O_RDONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return None;
+        }
+        let read = libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len());
+        libc::close(fd);
+        if read < 0 {
+            None
+        } else {
+            Some(read as usize)
+        }
+    }
+}
+
+/// Parses the leading decimal number of a sysfs value such as `"536870912\n"`
+#[cfg(target_os = "linux")]
+pub(crate) fn parse_leading_u64(bytes: &[u8]) -> Option<u64> {
+    let digits = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    bytes[..digits]
+        .iter()
+        .try_fold(0u64, |acc, &b| acc.checked_mul(10)?.checked_add((b - b'0') as u64))
+}
+
+/// Finds a `Key:   123456 kB` line in meminfo-style text and returns the value in bytes
+#[cfg(target_os = "linux")]
+fn parse_meminfo_bytes(text: &[u8], key: &[u8]) -> Option<u64> {
+    let start = text.windows(key.len()).position(|window| window == key)? + key.len();
+    let value = &text[start..];
+    let digits_at = value.iter().position(|b| b.is_ascii_digit())?;
+    parse_leading_u64(&value[digits_at..]).map(|kb| kb * 1024)
+}
+
+/// Hierarchy a cgroup file lives in: the v2 unified one or a v1 controller's
+#[cfg(target_os = "linux")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CgroupHierarchy {
+    Unified,
+    Memory,
+    Cpu,
+}
+
+#[cfg(target_os = "linux")]
+impl CgroupHierarchy {
+    const fn mount(self) -> &'static [u8] {
+        match self {
+            CgroupHierarchy::Unified => b"/sys/fs/cgroup",
+            CgroupHierarchy::Memory => b"/sys/fs/cgroup/memory",
+            CgroupHierarchy::Cpu => b"/sys/fs/cgroup/cpu",
+        }
+    }
+
+    /// Whether a `<id>:<controllers>` pair of `/proc/self/cgroup` names this hierarchy
+    fn matches(self, id: &[u8], controllers: &[u8]) -> bool {
+        let controller: &[u8] = match self {
+            CgroupHierarchy::Unified => return id == b"0" && controllers.is_empty(),
+            CgroupHierarchy::Memory => b"memory",
+            CgroupHierarchy::Cpu => b"cpu",
+        };
+        controllers.split(|&b| b == b',').any(|name| name == controller)
+    }
+}
+
+/// Longest cgroup file path built on the stack
+#[cfg(target_os = "linux")]
+pub(crate) const CGROUP_PATH_MAX: usize = 512;
+
+/// Builds the NUL-terminated path of a cgroup file in `out`
+///
+/// With `own`, the directory is this process's cgroup as listed in `/proc/self/cgroup` (the
+/// `0::` entry for v2, the controller's entry for v1); otherwise it is the hierarchy root.
+/// Allocation-free, so it is safe to call from inside the global allocator.
+#[cfg(target_os = "linux")]
+pub(crate) fn cgroup_file_path<'a>(
+    hierarchy: CgroupHierarchy,
+    file: &[u8],
+    own: bool,
+    out: &'a mut [u8; CGROUP_PATH_MAX],
+) -> Option<&'a core::ffi::CStr> {
+    let mut membership = [0u8; 2048];
+    let dir: &[u8] = if own {
+        let len = read_file_into(c"/proc/self/cgroup", &mut membership)?;
+        membership[..len].split(|&b| b == b'\n').find_map(|line| {
+            // "<id>:<controllers>:<path>"
+            let mut fields = line.splitn(3, |&b| b == b':');
+            let (id, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+            hierarchy.matches(id, controllers).then_some(path)
+        })?
+    } else {
+        b""
+    };
+    let dir = if dir == b"/" { b"" } else { dir };
+
+    let parts: [&[u8]; 5] = [hierarchy.mount(), dir, b"/", file, b"\0"];
+    let mut len = 0;
+    for part in parts {
+        out.get_mut(len..len + part.len())?.copy_from_slice(part);
+        len += part.len();
+    }
+    core::ffi::CStr::from_bytes_with_nul(&out[..len]).ok()
+}
+
+/// Reads a file of this process's cgroup, falling back to the hierarchy root when the
+/// process's own directory is not visible (e.g. a container without a cgroup namespace)
+#[cfg(target_os = "linux")]
+fn read_cgroup_file(hierarchy: CgroupHierarchy, file: &[u8], buf: &mut [u8]) -> Option<usize> {
+    let mut path = [0u8; CGROUP_PATH_MAX];
+    [true, false]
+        .into_iter()
+        .find_map(|own| read_file_into(cgroup_file_path(hierarchy, file, own, &mut path)?, buf))
+}
+
+/// Reads a cgroup memory value, treating `max` and near-`i64::MAX` sentinels as unlimited
+#[cfg(target_os = "linux")]
+fn read_cgroup_bytes(v2_file: &[u8], v1_file: &[u8]) -> Option<u64> {
+    let mut buf = [0u8; 32];
+    let len = read_cgroup_file(CgroupHierarchy::Unified, v2_file, &mut buf)
+        .or_else(|| read_cgroup_file(CgroupHierarchy::Memory, v1_file, &mut buf))?;
+    let value = parse_leading_u64(&buf[..len])?;
+    // cgroup v1 reports "unlimited" as a page-aligned value close to i64::MAX
+    if value >= (1u64 << 62) {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// Returns the memory limit of the current cgroup (v2 `memory.max` or v1 `memory.limit_in_bytes`)
+///
+/// Returns `None` when no limit is configured or cgroups are unavailable.
+/// Allocation-free, so it is safe to call during global allocator initialization.
+#[cfg(not(target_os = "none"))]
+pub(crate) fn get_cgroup_memory_limit() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        read_cgroup_bytes(b"memory.max", b"memory.limit_in_bytes")
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Returns the memory currently charged to the current cgroup
+///
+/// Allocation-free, so it is safe to call from inside the global allocator.
+#[cfg(all(any(feature = "memory-pressure", feature = "oom-diagnostics"), not(target_os = "none")))]
+pub(crate) fn get_cgroup_memory_usage() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        read_cgroup_bytes(b"memory.current", b"memory.usage_in_bytes")
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        None
+    }
+}
+
+/// Returns memory the system can still hand out without swapping
+///
+/// Linux reads `MemAvailable` from `/proc/meminfo`, Windows uses `GlobalMemoryStatusEx`.
+/// Returns `None` where no reliable figure exists.
+#[cfg(all(feature = "memory-pressure", not(target_os = "none")))]
+pub(crate) fn get_available_memory() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        // MemAvailable is the third line, well inside the first 512 bytes
+        let mut buf = [0u8; 512];
+        let len = read_file_into(c"/proc/meminfo", &mut buf)?;
+        parse_meminfo_bytes(&buf[..len], b"MemAvailable:")
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::mem;
+        use winapi::um::sysinfoapi::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+        unsafe {
+            let mut mem_status: MEMORYSTATUSEX = mem::zeroed();
+            mem_status.dwLength = mem::size_of::<MEMORYSTATUSEX>() as u32;
+            if GlobalMemoryStatusEx(&mut mem_status) != 0 {
+                return Some(mem_status.ullAvailPhys);
+            }
+        }
+        None
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        None
+    }
+}
+
+/// Returns the memory actually available to this process: the cgroup limit if one is set
+/// and lower than physical memory, otherwise total physical memory
+#[cfg(not(target_os = "none"))]
+pub(crate) fn get_effective_memory_limit() -> u64 {
+    let total = get_total_memory_safe();
+    match get_cgroup_memory_limit() {
+        Some(limit) if limit < total => limit,
+        _ => total,
+    }
+}
+
+/// Returns the cores this process can keep busy: online cores narrowed by the affinity mask and
+/// the cgroup CPU quota (v2 `cpu.max` or v1 `cpu.cfs_quota_us`), rounded up, at least 1
+///
+/// Allocation-free, so it is safe to call during global allocator initialization.
+#[cfg(target_os = "linux")]
+pub(crate) fn get_effective_cpu_cores() -> usize {
+    let mut cores = crate::platform::get_cpu_cores_safe();
+    unsafe {
+        let mut set: libc::cpu_set_t = core::mem::zeroed();
+        if libc::sched_getaffinity(0, core::mem::size_of::<libc::cpu_set_t>(), &mut set) == 0 {
+            cores = cores.min(libc::CPU_COUNT(&set) as usize);
+        }
+    }
+
+    let mut buf = [0u8; 64];
+    let quota = if let Some(len) = read_cgroup_file(CgroupHierarchy::Unified, b"cpu.max", &mut buf) {
+        // "<quota> <period>", or "max <period>" without a limit
+        let text = &buf[..len];
+        let period_at = text.iter().position(|&b| b == b' ').map_or(len, |space| space + 1);
+        parse_leading_u64(text).zip(parse_leading_u64(&text[period_at..]))
+    } else {
+        let mut period = [0u8; 32];
+        read_cgroup_file(CgroupHierarchy::Cpu, b"cpu.cfs_quota_us", &mut buf)
+            .and_then(|len| parse_leading_u64(&buf[..len]))
+            .zip(
+                read_cgroup_file(CgroupHierarchy::Cpu, b"cpu.cfs_period_us", &mut period)
+                    .and_then(|len| parse_leading_u64(&period[..len])),
+            )
+    };
+    if let Some((quota, period)) = quota {
+        if period > 0 {
+            cores = cores.min(quota.div_ceil(period) as usize);
+        }
+    }
+
+    cores.max(1)
+}
+
+// ========== Huge Pages ==========
+
+/// Returns the transparent huge page mode, the bracketed word of
+/// `/sys/kernel/mm/transparent_hugepage/enabled` (e.g. `always [madvise] never`)
+///
+/// Allocation-free, so it is safe to call from inside the global allocator.
+pub(crate) fn get_transparent_huge_pages() -> TransparentHugePages {
+    #[cfg(target_os = "linux")]
+    {
+        let mut buf = [0u8; 64];
+        let Some(len) = read_file_into(c"/sys/kernel/mm/transparent_hugepage/enabled", &mut buf) else {
+            return TransparentHugePages::Unsupported;
+        };
+        let text = &buf[..len];
+        let selected = text
+            .iter()
+            .position(|&b| b == b'[')
+            .and_then(|open| {
+                let close = text[open..].iter().position(|&b| b == b']')?;
+                Some(&text[open + 1..open + close])
+            });
+        match selected {
+            Some(b"always") => TransparentHugePages::Always,
+            Some(b"madvise") => TransparentHugePages::Madvise,
+            Some(b"never") => TransparentHugePages::Never,
+            _ => TransparentHugePages::Unsupported,
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        TransparentHugePages::Unsupported
+    }
+}
+
+/// Lists huge page sizes from the `hugepages-<size>kB` entries of `/sys/kernel/mm/hugepages`
+#[cfg(not(target_os = "none"))]
+fn get_huge_page_sizes() -> Vec<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let Ok(entries) = std::fs::read_dir("/sys/kernel/mm/hugepages") else {
+            return Vec::new();
+        };
+        let mut sizes: Vec<u64> = entries
+            .filter_map(|entry| {
+                let name = entry.ok()?.file_name();
+                let kb = name.as_encoded_bytes().strip_prefix(b"hugepages-")?;
+                parse_leading_u64(kb).map(|kb| kb * 1024)
+            })
+            .collect();
+        sizes.sort_unstable();
+        sizes
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Vec::new()
+    }
+}
+
+// ========== NUMA Topology ==========
+
+/// Calls `f` with every node number in `/sys/devices/system/node/online` (e.g. `0-1,4`)
+#[cfg(target_os = "linux")]
+fn for_each_online_numa_node(mut f: impl FnMut(usize)) {
+    let mut buf = [0u8; 256];
+    let Some(len) = read_file_into(c"/sys/devices/system/node/online", &mut buf) else {
+        return;
+    };
+
+    for range in buf[..len].split(|&b| b == b',') {
+        let range = range.trim_ascii();
+        let mut bounds = range.splitn(2, |&b| b == b'-');
+        let Some(first) = bounds.next().and_then(parse_leading_u64) else {
+            continue;
+        };
+        let last = bounds.next().and_then(parse_leading_u64).unwrap_or(first);
+        for node in first..=last {
+            f(node as usize);
+        }
+    }
+}
+
+/// Returns the number of online NUMA nodes, 1 when the topology is unknown
+///
+/// Allocation-free, so it is safe to call during global allocator initialization.
+pub(crate) fn get_numa_node_count_safe() -> usize {
+    #[cfg(target_os = "linux")]
+    {
+        let mut count = 0;
+        for_each_online_numa_node(|_| count += 1);
+        count.max(1)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        1
+    }
+}
+
+/// Returns the memory attached to a NUMA node, from `MemTotal` in its `meminfo`
+///
+/// Allocation-free: the path is built in a stack buffer.
+#[cfg(target_os = "linux")]
+fn get_numa_node_memory_safe(node: usize) -> Option<u64> {
+    const PREFIX: &[u8] = b"/sys/devices/system/node/node";
+    const SUFFIX: &[u8] = b"/meminfo\0";
+
+    let mut digits = [0u8; 20];
+    let mut digit_count = 0;
+    let mut rest = node;
+    loop {
+        digits[digits.len() - 1 - digit_count] = b'0' + (rest % 10) as u8;
+        digit_count += 1;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+
+    let mut path = [0u8; 64];
+    let parts: [&[u8]; 3] = [PREFIX, &digits[digits.len() - digit_count..], SUFFIX];
+    let mut len = 0;
+    for part in parts {
+        path[len..len + part.len()].copy_from_slice(part);
+        len += part.len();
+    }
+    let path = core::ffi::CStr::from_bytes_with_nul(&path[..len]).ok()?;
+
+    // MemTotal is the first line
+    let mut buf = [0u8; 128];
+    let read = read_file_into(path, &mut buf)?;
+    parse_meminfo_bytes(&buf[..read], b"MemTotal:")
+}
+
+/// Lists the online NUMA nodes with their memory
+#[cfg(not(target_os = "none"))]
+fn get_numa_nodes() -> Vec<NumaNode> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut nodes = Vec::new();
+        for_each_online_numa_node(|id| {
+            nodes.push(NumaNode {
+                id,
+                total_memory_bytes: get_numa_node_memory_safe(id).unwrap_or(0),
+            })
+        });
+        nodes
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        Vec::new()
+    }
+}
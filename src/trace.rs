@@ -0,0 +1,628 @@
+use core::alloc::Layout;
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::collections::BTreeMap;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use crate::backend::BackendAllocator;
+use crate::reentrancy::ReentrancyGuard;
+use crate::runtime::RuntimeAllocator;
+use crate::types::AllocatorType;
+// ========== Allocation Trace Recording ==========
+
+/// First bytes of every trace file, ending in the format version
+const MAGIC: &[u8; 8] = b"AATRACE\x01";
+
+/// Encoded events buffered before they are written out
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Largest encoded event: tag, four varints and the alignment
+const MAX_EVENT_SIZE: usize = 1 + 4 * 10 + 1;
+
+const ALLOC: u8 = 0;
+const DEALLOC: u8 = 1;
+const REALLOC: u8 = 2;
+
+/// Totals of a finished trace, returned by [`stop_trace()`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TraceSummary {
+    /// Allocations, deallocations and reallocations recorded
+    pub events: u64,
+
+    /// Size of the trace file, header included
+    pub bytes: u64,
+}
+
+struct Tracer {
+    fd: i32,
+    start: Instant,
+    last_ns: u64,
+    buf: [u8; BUFFER_SIZE],
+    len: usize,
+    // Object IDs of live blocks by address; freed IDs are reused, keeping IDs no larger than
+    // the peak number of live blocks so the replay can index a dense table
+    objects: BTreeMap<usize, u64>,
+    free_ids: Vec<u64>,
+    next_id: u64,
+    events: u64,
+    bytes: u64,
+    error: Option<i32>,
+}
+
+static TRACER: Mutex<Option<Tracer>> = Mutex::new(None);
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+static EXIT_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+static NEXT_THREAD: AtomicU32 = AtomicU32::new(1);
+
+thread_local! {
+    static THREAD_ID: Cell<u32> = const { Cell::new(0) };
+    static IN_REALLOC: Cell<bool> = const { Cell::new(false) };
+}
+
+fn lock_tracer() -> MutexGuard<'static, Option<Tracer>> {
+    TRACER.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Small sequential ID of the calling thread, 0 once thread-local storage is gone
+fn thread_id() -> u32 {
+    THREAD_ID
+        .try_with(|id| {
+            if id.get() == 0 {
+                id.set(NEXT_THREAD.fetch_add(1, Ordering::Relaxed));
+            }
+            id.get()
+        })
+        .unwrap_or(0)
+}
+
+fn put_varint(buf: &mut [u8], len: &mut usize, mut value: u64) {
+    while value >= 0x80 {
+        buf[*len] = value as u8 | 0x80;
+        *len += 1;
+        value >>= 7;
+    }
+    buf[*len] = value as u8;
+    *len += 1;
+}
+
+/// Writes all of `bytes`, returning the OS error code on failure
+fn write_all(fd: i32, mut bytes: &[u8]) -> Result<(), i32> {
+    while !bytes.is_empty() {
+        let written = unsafe { libc::write(fd, bytes.as_ptr() as *const libc::c_void, bytes.len()) };
+        if written < 0 {
+            let error = io::Error::last_os_error();
+            if error.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(error.raw_os_error().unwrap_or(libc::EIO));
+        }
+        bytes = &bytes[written as usize..];
+    }
+    Ok(())
+}
+
+impl Tracer {
+    fn new(fd: i32) -> Self {
+        let mut tracer = Tracer {
+            fd,
+            start: Instant::now(),
+            last_ns: 0,
+            buf: [0; BUFFER_SIZE],
+            len: 0,
+            objects: BTreeMap::new(),
+            free_ids: Vec::new(),
+            next_id: 0,
+            events: 0,
+            bytes: 0,
+            error: None,
+        };
+        tracer.buf[..MAGIC.len()].copy_from_slice(MAGIC);
+        tracer.len = MAGIC.len();
+        tracer
+    }
+
+    fn flush(&mut self) {
+        if self.len == 0 {
+            return;
+        }
+        if self.error.is_none() {
+            if let Err(code) = write_all(self.fd, &self.buf[..self.len]) {
+                self.error = Some(code);
+            }
+        }
+        self.bytes += self.len as u64;
+        self.len = 0;
+    }
+
+    /// Starts an event: tag, thread and time since the previous event
+    fn begin(&mut self, kind: u8) {
+        if self.len + MAX_EVENT_SIZE > BUFFER_SIZE {
+            self.flush();
+        }
+        let now_ns = self.start.elapsed().as_nanos() as u64;
+        let delta_ns = now_ns.saturating_sub(self.last_ns);
+        self.last_ns = now_ns.max(self.last_ns);
+        self.events += 1;
+
+        self.buf[self.len] = kind;
+        self.len += 1;
+        let thread = thread_id() as u64;
+        put_varint(&mut self.buf, &mut self.len, thread);
+        put_varint(&mut self.buf, &mut self.len, delta_ns);
+    }
+
+    fn put(&mut self, value: u64) {
+        put_varint(&mut self.buf, &mut self.len, value);
+    }
+
+    fn on_alloc(&mut self, addr: usize, layout: Layout) {
+        let id = self.free_ids.pop().unwrap_or_else(|| {
+            self.next_id += 1;
+            self.next_id - 1
+        });
+        self.objects.insert(addr, id);
+
+        self.begin(ALLOC);
+        self.put(id);
+        self.put(layout.size() as u64);
+        self.buf[self.len] = layout.align().trailing_zeros() as u8;
+        self.len += 1;
+    }
+
+    fn on_dealloc(&mut self, addr: usize) {
+        // Blocks allocated before the trace started are not part of it
+        let Some(id) = self.objects.remove(&addr) else {
+            return;
+        };
+        self.free_ids.push(id);
+
+        self.begin(DEALLOC);
+        self.put(id);
+    }
+
+    fn on_realloc(&mut self, old_addr: usize, new_addr: usize, layout: Layout, new_size: usize) {
+        let Some(id) = self.objects.remove(&old_addr) else {
+            // The replay never saw the original block, so it starts here
+            let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+            return self.on_alloc(new_addr, new_layout);
+        };
+        self.objects.insert(new_addr, id);
+
+        self.begin(REALLOC);
+        self.put(id);
+        self.put(new_size as u64);
+    }
+}
+
+/// Records an allocation, called from `RuntimeAllocator::alloc`
+#[inline]
+pub(crate) fn record_alloc(ptr: *mut u8, layout: Layout) {
+    if !ACTIVE.load(Ordering::Relaxed) || ptr.is_null() || in_realloc() {
+        return;
+    }
+    let Some(_guard) = ReentrancyGuard::enter() else {
+        return;
+    };
+    if let Some(tracer) = lock_tracer().as_mut() {
+        tracer.on_alloc(ptr as usize, layout);
+    }
+}
+
+/// Records a deallocation, called from `RuntimeAllocator::dealloc`
+#[inline]
+pub(crate) fn record_dealloc(ptr: *mut u8) {
+    if !ACTIVE.load(Ordering::Relaxed) || in_realloc() {
+        return;
+    }
+    let Some(_guard) = ReentrancyGuard::enter() else {
+        return;
+    };
+    if let Some(tracer) = lock_tracer().as_mut() {
+        tracer.on_dealloc(ptr as usize);
+    }
+}
+
+/// Records a successful reallocation, called from `RuntimeAllocator::realloc`
+#[inline]
+pub(crate) fn record_realloc(old_ptr: *mut u8, new_ptr: *mut u8, layout: Layout, new_size: usize) {
+    if !ACTIVE.load(Ordering::Relaxed) || new_ptr.is_null() {
+        return;
+    }
+    let Some(_guard) = ReentrancyGuard::enter() else {
+        return;
+    };
+    if let Some(tracer) = lock_tracer().as_mut() {
+        tracer.on_realloc(old_ptr as usize, new_ptr as usize, layout, new_size);
+    }
+}
+
+fn in_realloc() -> bool {
+    IN_REALLOC.try_with(Cell::get).unwrap_or(false)
+}
+
+/// Runs `f` with the allocations and deallocations it makes left out of the trace, so a
+/// reallocation is recorded as one event
+#[inline]
+pub(crate) fn as_one_realloc<R>(f: impl FnOnce() -> R) -> R {
+    let outer = IN_REALLOC.try_with(|active| active.replace(true)).unwrap_or(true);
+    let result = f();
+    if !outer {
+        let _ = IN_REALLOC.try_with(|active| active.set(false));
+    }
+    result
+}
+
+fn open_trace(path: &core::ffi::CStr) -> io::Result<()> {
+    let fd = unsafe {
+        libc::open(
+            path.as_ptr(),
+            libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC | libc::O_CLOEXEC,
+            0o644 as libc::c_uint,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let previous = {
+        let _guard = ReentrancyGuard::enter();
+        let mut tracer = lock_tracer();
+        let previous = tracer.take();
+        *tracer = Some(Tracer::new(fd));
+        previous
+    };
+    ACTIVE.store(true, Ordering::Release);
+    install_exit_hook();
+    if let Some(mut previous) = previous {
+        previous.flush();
+        unsafe { libc::close(previous.fd) };
+    }
+    Ok(())
+}
+
+/// Starts the trace requested with `AUTO_ALLOCATOR_TRACE`, called once when the allocator
+/// initializes
+pub(crate) fn start_from_env() {
+    let path = unsafe { libc::getenv(c"AUTO_ALLOCATOR_TRACE".as_ptr()) };
+    if path.is_null() {
+        return;
+    }
+    let path = unsafe { core::ffi::CStr::from_ptr(path) };
+    if !path.is_empty() && open_trace(path).is_err() {
+        crate::rawlog::write_stderr(b"[WARN] Auto-allocator: failed to open AUTO_ALLOCATOR_TRACE\n");
+    }
+}
+
+fn install_exit_hook() {
+    if EXIT_HOOK_INSTALLED
+        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+        .is_ok()
+    {
+        unsafe { libc::atexit(finish_at_exit) };
+    }
+}
+
+extern "C" fn finish_at_exit() {
+    let _ = stop_trace();
+}
+
+/// Starts recording every allocation, deallocation and reallocation into a trace file
+///
+/// The trace records size, alignment, thread, timestamp and an object ID for each event in a
+/// compact binary format. Events go through a fixed 64 KiB buffer that is written out when it
+/// fills, so recording never holds more than that in memory besides the table of live blocks.
+/// A trace already being recorded is finished first. Setting `AUTO_ALLOCATOR_TRACE` to a path
+/// records the whole run instead; the trace is completed when the process exits.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// auto_allocator::start_trace("workload.trace").unwrap();
+/// let data: Vec<Vec<u8>> = (0..1000).map(|i| vec![0; i]).collect();
+/// drop(data);
+/// let summary = auto_allocator::stop_trace().unwrap();
+/// println!("{} events recorded", summary.events);
+/// ```
+pub fn start_trace(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = std::ffi::CString::new(path.as_ref().as_os_str().as_encoded_bytes())
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+    open_trace(&path)
+}
+
+/// Stops recording, writes the buffered events and closes the trace file
+///
+/// Returns an empty summary when no trace is being recorded.
+pub fn stop_trace() -> io::Result<TraceSummary> {
+    ACTIVE.store(false, Ordering::Release);
+    let tracer = {
+        let _guard = ReentrancyGuard::enter();
+        lock_tracer().take()
+    };
+    let Some(mut tracer) = tracer else {
+        return Ok(TraceSummary::default());
+    };
+
+    let _guard = ReentrancyGuard::enter();
+    tracer.flush();
+    unsafe { libc::close(tracer.fd) };
+    match tracer.error {
+        Some(code) => Err(io::Error::from_raw_os_error(code)),
+        None => Ok(TraceSummary { events: tracer.events, bytes: tracer.bytes }),
+    }
+}
+
+// ========== Trace Replay ==========
+
+/// Events decoded ahead of each timed replay step, so decoding is not timed
+const REPLAY_BATCH: usize = 4096;
+
+/// Stride at which replayed blocks are written, making their memory resident like the
+/// program's own
+const TOUCH_STRIDE: usize = 4096;
+
+/// Outcome of replaying a trace against one backend, returned by [`replay_trace()`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayReport {
+    /// Backend the trace was replayed against
+    pub allocator_type: AllocatorType,
+
+    /// Events replayed
+    pub events: u64,
+
+    /// Time spent in the backend's allocate, free and reallocate calls
+    pub elapsed: Duration,
+
+    /// Largest number of bytes live at once in the trace
+    pub peak_live_bytes: u64,
+
+    /// Peak resident set size of the process at the end of the replay
+    pub peak_rss_bytes: u64,
+
+    /// Share of the memory the replay added at its peak that did not hold live data, from
+    /// 0.0 (none wasted) to 1.0
+    pub fragmentation: f64,
+}
+
+#[derive(Clone, Copy)]
+enum Event {
+    Alloc { id: usize, layout: Layout },
+    Dealloc { id: usize },
+    Realloc { id: usize, size: usize },
+}
+
+struct TraceReader<R> {
+    input: R,
+}
+
+impl<R: Read> TraceReader<R> {
+    fn byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8];
+        match self.input.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    fn required_byte(&mut self) -> io::Result<u8> {
+        self.byte()?.ok_or_else(|| invalid("trace ends in the middle of an event"))
+    }
+
+    fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.required_byte()?;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint longer than 64 bits"))
+    }
+
+    fn usize(&mut self) -> io::Result<usize> {
+        usize::try_from(self.varint()?).map_err(|_| invalid("value does not fit in usize"))
+    }
+
+    fn next_event(&mut self) -> io::Result<Option<Event>> {
+        let Some(kind) = self.byte()? else {
+            return Ok(None);
+        };
+        // Thread and timestamp are kept for analysis; the replay runs in recorded order
+        self.varint()?;
+        self.varint()?;
+        let id = self.usize()?;
+        let event = match kind {
+            ALLOC => {
+                let size = self.usize()?;
+                let align = 1usize
+                    .checked_shl(self.required_byte()? as u32)
+                    .ok_or_else(|| invalid("alignment out of range"))?;
+                let layout = Layout::from_size_align(size, align).map_err(|_| invalid("invalid layout"))?;
+                Event::Alloc { id, layout }
+            }
+            DEALLOC => Event::Dealloc { id },
+            REALLOC => Event::Realloc { id, size: self.usize()? },
+            _ => return Err(invalid("unknown event kind")),
+        };
+        Ok(Some(event))
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Process high-water mark of resident memory in bytes
+fn max_rss_bytes() -> u64 {
+    unsafe {
+        let mut usage: libc::rusage = core::mem::zeroed();
+        if libc::getrusage(libc::RUSAGE_SELF, &mut usage) != 0 {
+            return 0;
+        }
+        // Reported in KiB everywhere except macOS, which uses bytes
+        if cfg!(target_os = "macos") {
+            usage.ru_maxrss as u64
+        } else {
+            usage.ru_maxrss as u64 * 1024
+        }
+    }
+}
+
+unsafe fn touch(ptr: *mut u8, from: usize, to: usize) {
+    let mut offset = from;
+    while offset < to {
+        ptr.add(offset).write_volatile(0);
+        offset += TOUCH_STRIDE;
+    }
+}
+
+/// Replays the live blocks of one trace against a single backend
+struct Replay {
+    allocator_id: u8,
+    blocks: Vec<Option<(*mut u8, Layout)>>,
+    live_bytes: u64,
+    peak_live_bytes: u64,
+}
+
+impl Replay {
+    unsafe fn apply(&mut self, event: Event) -> io::Result<()> {
+        match event {
+            Event::Alloc { id, layout } => {
+                let ptr = RuntimeAllocator::alloc_with(self.allocator_id, layout);
+                if ptr.is_null() {
+                    return Err(io::ErrorKind::OutOfMemory.into());
+                }
+                touch(ptr, 0, layout.size());
+                if let Some((old_ptr, old_layout)) = self.blocks[id].replace((ptr, layout)) {
+                    // A truncated trace can reuse an ID whose free was never written
+                    self.free(old_ptr, old_layout);
+                }
+                self.live_bytes += layout.size() as u64;
+                self.peak_live_bytes = self.peak_live_bytes.max(self.live_bytes);
+            }
+            Event::Dealloc { id } => {
+                if let Some((ptr, layout)) = self.blocks[id].take() {
+                    self.free(ptr, layout);
+                }
+            }
+            Event::Realloc { id, size } => {
+                let Some((ptr, layout)) = self.blocks[id] else {
+                    return Ok(());
+                };
+                let new_layout = Layout::from_size_align(size, layout.align())
+                    .map_err(|_| invalid("invalid layout"))?;
+                // Same steps as RuntimeAllocator::realloc: allocate, copy, free
+                let new_ptr = RuntimeAllocator::alloc_with(self.allocator_id, new_layout);
+                if new_ptr.is_null() {
+                    return Err(io::ErrorKind::OutOfMemory.into());
+                }
+                core::ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(size));
+                touch(new_ptr, layout.size().next_multiple_of(TOUCH_STRIDE), size);
+                RuntimeAllocator::dealloc_with(self.allocator_id, ptr, layout);
+                self.blocks[id] = Some((new_ptr, new_layout));
+                self.live_bytes = self.live_bytes - layout.size() as u64 + size as u64;
+                self.peak_live_bytes = self.peak_live_bytes.max(self.live_bytes);
+            }
+        }
+        Ok(())
+    }
+
+    unsafe fn free(&mut self, ptr: *mut u8, layout: Layout) {
+        RuntimeAllocator::dealloc_with(self.allocator_id, ptr, layout);
+        self.live_bytes -= layout.size() as u64;
+    }
+}
+
+impl Drop for Replay {
+    fn drop(&mut self) {
+        for (ptr, layout) in self.blocks.iter_mut().filter_map(Option::take) {
+            unsafe { RuntimeAllocator::dealloc_with(self.allocator_id, ptr, layout) };
+        }
+    }
+}
+
+/// Replays a recorded trace against one backend, measuring time, peak RSS and fragmentation
+///
+/// Events are replayed on the calling thread in recorded order, straight against the backend
+/// rather than the selected global allocator. Every replayed block is written once per page,
+/// so its memory becomes resident as it was in the recorded program. Peak RSS is the process
+/// high-water mark, so for comparable figures replay each backend in a fresh process, as the
+/// `trace_replay` example does. Backends not compiled into this build return
+/// [`io::ErrorKind::Unsupported`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use auto_allocator::{replay_trace, AllocatorType};
+///
+/// let report = replay_trace("workload.trace", AllocatorType::System).unwrap();
+/// println!("{:?}: {:?}, {:.1}% fragmentation", report.allocator_type, report.elapsed,
+///     report.fragmentation * 100.0);
+/// ```
+pub fn replay_trace(path: impl AsRef<Path>, allocator_type: AllocatorType) -> io::Result<ReplayReport> {
+    let backend = BackendAllocator::new(allocator_type).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{:?} is not available in this build", allocator_type),
+        )
+    })?;
+    let allocator_id = backend.allocator_id();
+
+    let mut reader = TraceReader { input: BufReader::new(std::fs::File::open(path)?) };
+    let mut magic = [0u8; 8];
+    reader.input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not an auto-allocator trace"));
+    }
+
+    let rss_before = max_rss_bytes();
+    let mut replay = Replay { allocator_id, blocks: Vec::new(), live_bytes: 0, peak_live_bytes: 0 };
+    let mut batch = Vec::with_capacity(REPLAY_BATCH);
+    let mut events = 0u64;
+    let mut elapsed = Duration::ZERO;
+    loop {
+        batch.clear();
+        while batch.len() < REPLAY_BATCH {
+            let Some(event) = reader.next_event()? else {
+                break;
+            };
+            let (Event::Alloc { id, .. } | Event::Dealloc { id } | Event::Realloc { id, .. }) = event;
+            // Ids are handed out densely, so none can exceed the events before it
+            if id as u64 > events + batch.len() as u64 {
+                return Err(invalid("object id out of range"));
+            }
+            if id >= replay.blocks.len() {
+                replay.blocks.resize(id + 1, None);
+            }
+            batch.push(event);
+        }
+        if batch.is_empty() {
+            break;
+        }
+
+        let started = Instant::now();
+        for &event in &batch {
+            unsafe { replay.apply(event)? };
+        }
+        elapsed += started.elapsed();
+        events += batch.len() as u64;
+    }
+
+    let peak_rss_bytes = max_rss_bytes();
+    let added = peak_rss_bytes.saturating_sub(rss_before);
+    let fragmentation = if added > replay.peak_live_bytes {
+        1.0 - replay.peak_live_bytes as f64 / added as f64
+    } else {
+        0.0
+    };
+    Ok(ReplayReport {
+        allocator_type,
+        events,
+        elapsed,
+        peak_live_bytes: replay.peak_live_bytes,
+        peak_rss_bytes,
+        fragmentation,
+    })
+}
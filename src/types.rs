@@ -16,7 +16,10 @@
 ///     _ => println!("Using other allocator"),
 /// }
 /// ```
+///
+/// New backends may be added in minor releases, so matches need a wildcard arm.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum AllocatorType {
 
     /// Security-hardened mimalloc allocator
@@ -44,6 +47,202 @@ pub enum AllocatorType {
     /// Operating system provided allocator, maximum compatibility.
     /// Selected for debug builds, WASM, mobile, and platforms with optimized native allocators.
     System,
+
+    /// Guard-page debugging allocator
+    ///
+    /// Places every allocation on its own pages next to an inaccessible guard page, so heap
+    /// overflows (or underflows) fault immediately. Very slow and memory hungry; only selected
+    /// with `AUTO_ALLOCATOR_BACKEND=debug-guard` when the `debug-guard` feature is enabled.
+    DebugGuard,
+}
+
+impl AllocatorType {
+    /// Maps an internal allocator ID to its type
+    pub(crate) const fn from_id(allocator_id: u8) -> Self {
+        match allocator_id {
+            6 => AllocatorType::DebugGuard,
+            5 => AllocatorType::MimallocSecure,
+            2 => AllocatorType::Mimalloc,
+            4 => AllocatorType::EmbeddedHeap,
+            _ => AllocatorType::System,
+        }
+    }
+}
+
+/// Per-operation timings the adaptive calibration measured for one allocator
+///
+/// Each figure is the mean time of one allocate-and-free pair in nanoseconds.
+#[cfg(not(target_os = "none"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackendTiming {
+    /// Allocator that was measured
+    pub allocator_type: AllocatorType,
+
+    /// Small blocks (16 to 512 bytes) on one thread
+    pub small_ns: u64,
+
+    /// Large blocks (64 KiB to 1 MiB) on one thread
+    pub large_ns: u64,
+
+    /// Small blocks on several threads at once
+    pub multi_thread_ns: u64,
+
+    /// Blocks allocated on one thread and freed on another
+    pub cross_thread_ns: u64,
+}
+
+#[cfg(not(target_os = "none"))]
+impl BackendTiming {
+    /// Sum of all four timings, the score the calibration ranks allocators by
+    pub fn total_ns(&self) -> u64 {
+        self.small_ns
+            .saturating_add(self.large_ns)
+            .saturating_add(self.multi_thread_ns)
+            .saturating_add(self.cross_thread_ns)
+    }
+}
+
+/// Outcome of the startup calibration run by the `adaptive` feature
+///
+/// A run that reuses a decision from the `selection-cache` reports the measurements saved with
+/// it, taken by the run that calibrated.
+#[cfg(not(target_os = "none"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calibration {
+    /// Timings of every measured allocator, fastest first
+    pub timings: Vec<BackendTiming>,
+
+    /// Threads used for the multi-threaded and cross-thread workloads
+    pub threads: usize,
+
+    /// Wall-clock time the whole calibration took
+    pub elapsed: std::time::Duration,
+}
+
+/// Allocation pattern an application declares so selection can weigh it against the hardware
+///
+/// Declared with [`declare_workload!`](crate::declare_workload), [`set_workload()`](crate::set_workload)
+/// before the first allocation, or `AUTO_ALLOCATOR_WORKLOAD` at startup (Unix). Only platforms
+/// that select at runtime consult it; debug builds, mobile and BSD keep their compile-time choice.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// auto_allocator::declare_workload!(auto_allocator::Workload::LowFootprint);
+/// ```
+#[cfg(not(target_os = "none"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Workload {
+    /// Many small objects freed soon after allocation, such as request parsing or ASTs:
+    /// mimalloc on any core count
+    ManySmallShortLived,
+
+    /// Mostly multi-megabyte buffers: the system allocator on hosts with 2 cores or fewer, where
+    /// it maps large blocks directly and unmaps them on free; mimalloc elsewhere
+    LargeBuffers,
+
+    /// Blocks regularly freed by a different thread than the one that allocated them, such as
+    /// producer/consumer pipelines: mimalloc on any core count
+    CrossThreadFree,
+
+    /// Resident memory matters more than speed: the system allocator on hosts with 4 cores or
+    /// fewer or less than 4 GiB of memory; elsewhere mimalloc set to return freed memory to the
+    /// OS immediately
+    LowFootprint,
+
+    /// Predictable allocation latency matters most: mimalloc on any core count, keeping freed
+    /// memory committed longer so the hot path does not fault pages back in
+    LatencyCritical,
+}
+
+/// Security profile trading allocation speed for heap hardening
+///
+/// Chosen at compile time with the `profile-balanced`, `profile-hardened` or `profile-paranoid`
+/// features, or at startup with `AUTO_ALLOCATOR_PROFILE` when the `security-profiles` feature is
+/// enabled. Each level includes everything the previous one does; what actually takes effect
+/// depends on the selected backend and is reported as [`SecurityFeatures`].
+///
+/// # Example
+///
+/// ```rust
+/// use auto_allocator::SecurityProfile;
+///
+/// let info = auto_allocator::get_allocator_info();
+/// if info.security_profile >= SecurityProfile::Hardened {
+///     println!("Freed memory is poisoned: {}", info.security_features.free_poisoning);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SecurityProfile {
+    /// Fastest allocation, no hardening beyond what the backend does by default; secure mimalloc
+    /// is not selected automatically
+    Performance,
+
+    /// Security-hardened mimalloc where it is compiled in (guard pages between mimalloc pages,
+    /// encoded and randomized free lists), the system allocator otherwise; the default with the
+    /// `secure` feature
+    Balanced,
+
+    /// Balanced, plus freed blocks filled with a poison pattern and a trailing guard page on
+    /// large allocations
+    Hardened,
+
+    /// Hardened, with freed blocks wiped to zero instead of poisoned
+    Paranoid,
+}
+
+/// Hardening actually in effect for the active profile and backend
+///
+/// A profile asks for protections; a backend may not offer them all. For example randomized
+/// allocation comes from mimalloc's secure mode, so it is off when the system allocator is used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SecurityFeatures {
+    /// mimalloc is built in secure mode
+    pub secure_mimalloc: bool,
+
+    /// Freed blocks are filled with a poison pattern before the backend reuses them (the
+    /// `hardened` profile or the `quarantine` feature)
+    pub free_poisoning: bool,
+
+    /// Freed blocks are wiped to zero before the backend reuses them (the `paranoid` profile or
+    /// the `zero-on-free` feature), unless poisoning overwrites the wipe
+    pub zero_on_free: bool,
+
+    /// Large allocations end at an inaccessible guard page
+    pub large_guard_pages: bool,
+
+    /// The backend hands out blocks in a randomized order, which only secure mimalloc does
+    pub randomized_allocation: bool,
+}
+
+/// glibc malloc parameters set with `mallopt` when the system allocator is selected
+///
+/// glibc's defaults let a threaded service grow up to 8 arenas per core and raise the `mmap`
+/// threshold as it runs, both known to bloat resident memory. Applied on Linux with glibc
+/// unless `AUTO_ALLOCATOR_GLIBC_TUNING=off`. A parameter the environment already sets
+/// (`MALLOC_ARENA_MAX`, `MALLOC_TRIM_THRESHOLD_`, `MALLOC_MMAP_THRESHOLD_` or the matching
+/// `GLIBC_TUNABLES` entry) is left alone and reported as `None`.
+///
+/// # Example
+///
+/// ```rust
+/// let info = auto_allocator::get_allocator_info();
+/// if let Some(tuning) = info.glibc_tuning {
+///     println!("glibc arenas capped at {:?}", tuning.arena_max);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GlibcTuning {
+    /// Maximum number of malloc arenas (`M_ARENA_MAX`), the effective core count but at least 2
+    pub arena_max: Option<usize>,
+
+    /// Free memory above this many bytes at the top of the heap is returned to the OS
+    /// (`M_TRIM_THRESHOLD`)
+    pub trim_threshold: Option<usize>,
+
+    /// Allocations of at least this many bytes are served by `mmap` (`M_MMAP_THRESHOLD`);
+    /// setting it also stops glibc from raising it dynamically
+    pub mmap_threshold: Option<usize>,
 }
 
 /// Allocator information structure
@@ -56,6 +255,11 @@ pub enum AllocatorType {
 /// - `allocator_type` - Currently used allocator type
 /// - `reason` - Detailed reason for allocator selection, including hardware information
 /// - `system_info` - System hardware and environment information
+/// - `security_profile` - Active [`SecurityProfile`]
+/// - `security_features` - Hardening the profile enables on the selected backend
+/// - `calibration` - Measurements behind an adaptive selection, if one ran
+/// - `workload` - Workload hint that decided the selection, if any
+/// - `glibc_tuning` - `mallopt` settings applied to the glibc system allocator, if any
 ///
 /// # Example
 ///
@@ -83,6 +287,96 @@ pub struct AllocatorInfo {
 
     /// System hardware and environment information
     pub system_info: SystemInfo,
+
+    /// Active security profile
+    pub security_profile: SecurityProfile,
+
+    /// Hardening in effect for the active profile on the selected allocator
+    pub security_features: SecurityFeatures,
+
+    /// Measurements behind the selection when the `adaptive` feature calibrated the allocators
+    #[cfg(not(target_os = "none"))]
+    pub calibration: Option<Calibration>,
+
+    /// Workload hint that decided the selection, if one was declared and consulted
+    #[cfg(not(target_os = "none"))]
+    pub workload: Option<Workload>,
+
+    /// glibc malloc parameters set because the system allocator was selected on glibc
+    #[cfg(not(target_os = "none"))]
+    pub glibc_tuning: Option<GlibcTuning>,
+}
+
+/// Transparent huge page mode of the kernel
+///
+/// Read from `/sys/kernel/mm/transparent_hugepage/enabled` on Linux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransparentHugePages {
+    /// Huge pages back every suitable anonymous mapping
+    Always,
+
+    /// Huge pages only back mappings advised with `madvise(MADV_HUGEPAGE)`
+    Madvise,
+
+    /// Transparent huge pages are disabled
+    Never,
+
+    /// The platform has no transparent huge pages, or the mode could not be read
+    Unsupported,
+}
+
+/// C library the process runs on, whose malloc is the system allocator
+///
+/// glibc is detected at runtime with `gnu_get_libc_version`, the others from the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LibcFlavor {
+    /// GNU C library with its runtime version, e.g. 2.39
+    Glibc {
+        /// Major version
+        major: u32,
+        /// Minor version
+        minor: u32,
+    },
+
+    /// musl, whose malloc is much slower than mimalloc once threads contend
+    Musl,
+
+    /// Android's bionic, where the system allocator is Scudo
+    Bionic,
+
+    /// Any other C library (macOS, Windows, BSD, WASM), or none at all
+    Other,
+}
+
+/// Debugging tool that only sees heap blocks coming from the system allocator
+///
+/// Detected at startup; while one is present, the system allocator is selected whatever the
+/// build and hardware, and backend tuning is skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebuggingTool {
+    /// AddressSanitizer, compiled in with `-Zsanitizer=address` or preloaded as `libasan`
+    AddressSanitizer,
+
+    /// ThreadSanitizer, compiled in with `-Zsanitizer=thread` or preloaded as `libtsan`
+    ThreadSanitizer,
+
+    /// Valgrind, detected with its `RUNNING_ON_VALGRIND` client request (x86_64, aarch64)
+    Valgrind,
+
+    /// The Miri interpreter, which cannot run mimalloc's C code
+    Miri,
+}
+
+/// Memory attached to one NUMA node
+///
+/// Read from `/sys/devices/system/node` on Linux.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumaNode {
+    /// Node number as used by the kernel (`node0`, `node1`, ...)
+    pub id: usize,
+
+    /// Physical memory attached to the node in bytes, 0 for memoryless nodes
+    pub total_memory_bytes: u64,
 }
 
 /// System information structure
@@ -95,9 +389,16 @@ pub struct AllocatorInfo {
 /// - `os_type` - Operating system type (linux, macos, windows, etc.)
 /// - `cpu_cores` - CPU core count (including hyperthreaded cores)
 /// - `total_memory_bytes` - Total memory in bytes
+/// - `effective_memory_bytes` - Memory available to the process, capped by its cgroup limit
 /// - `is_debug` - Whether this is a Debug build
 /// - `is_wasm` - Whether this is a WASM environment
 /// - `target_arch` - Target architecture (x86_64, aarch64, etc.)
+/// - `libc_flavor` - C library the process runs on (glibc with its version, musl, bionic)
+/// - `debugging_tool` - Sanitizer, Valgrind or Miri the process runs under
+/// - `transparent_huge_pages` - Transparent huge page mode (Linux)
+/// - `huge_page_sizes` - Huge page sizes supported by the kernel (Linux)
+/// - `numa_node_count` - Number of online NUMA nodes
+/// - `numa_nodes` - Memory of each online NUMA node (Linux)
 ///
 /// # Example
 ///
@@ -132,6 +433,13 @@ pub struct SystemInfo {
     /// Use [`format_memory_size()`] to format as human-readable string.
     pub total_memory_bytes: u64,
 
+    /// Memory available to this process in bytes
+    ///
+    /// The cgroup memory limit when one is set below physical memory, otherwise
+    /// [`total_memory_bytes`](Self::total_memory_bytes). Hosts short on it get the system
+    /// allocator or a mimalloc tuned for low overhead.
+    pub effective_memory_bytes: u64,
+
     /// Whether this is a Debug build
     ///
     /// Debug builds automatically select system allocator for faster compilation
@@ -149,5 +457,43 @@ pub struct SystemInfo {
     pub target_arch: String,
     #[cfg(target_os = "none")]
     pub target_arch: &'static str,
+
+    /// C library the process runs on
+    ///
+    /// On musl the crate prefers mimalloc even on a single core.
+    pub libc_flavor: LibcFlavor,
+
+    /// Sanitizer, Valgrind or Miri the process runs under, if any
+    ///
+    /// Forces the system allocator, since these tools track heap blocks through `malloc`.
+    pub debugging_tool: Option<DebuggingTool>,
+
+    /// Transparent huge page mode
+    ///
+    /// With the `huge-pages` feature, large allocations use huge pages when this allows it.
+    pub transparent_huge_pages: TransparentHugePages,
+
+    /// Huge page sizes supported by the kernel in bytes, smallest first
+    ///
+    /// Listed from `/sys/kernel/mm/hugepages`, e.g. `[2097152, 1073741824]` on x86_64.
+    /// Empty where huge pages are unsupported.
+    #[cfg(not(target_os = "none"))]
+    pub huge_page_sizes: Vec<u64>,
+    #[cfg(target_os = "none")]
+    pub huge_page_sizes: &'static [u64],
+
+    /// Number of online NUMA nodes
+    ///
+    /// 1 on single-node machines and wherever the topology cannot be read. With more than one
+    /// node, mimalloc is told to keep its arenas node-local.
+    pub numa_node_count: usize,
+
+    /// Online NUMA nodes with their memory, ordered by node number
+    ///
+    /// Empty where the topology cannot be read.
+    #[cfg(not(target_os = "none"))]
+    pub numa_nodes: Vec<NumaNode>,
+    #[cfg(target_os = "none")]
+    pub numa_nodes: &'static [NumaNode],
 }
 
@@ -0,0 +1,37 @@
+use core::sync::atomic::{compiler_fence, Ordering};
+// ========== Secure Memory Wiping ==========
+
+const WORD: usize = core::mem::size_of::<usize>();
+
+/// Overwrites `len` bytes at `ptr` with zeros in a way the optimizer cannot remove
+///
+/// A plain `write_bytes` right before a free is a dead store the compiler may drop. Volatile
+/// stores, followed by a compiler fence, are always emitted.
+#[inline]
+pub(crate) unsafe fn secure_wipe(ptr: *mut u8, len: usize) {
+    secure_fill(ptr, len, 0)
+}
+
+/// Overwrites `len` bytes at `ptr` with `byte`, with the same guarantee as [`secure_wipe()`]
+#[inline]
+pub(crate) unsafe fn secure_fill(ptr: *mut u8, len: usize, byte: u8) {
+    let word = usize::from_ne_bytes([byte; WORD]);
+    let mut offset = 0;
+
+    // Bytes up to the first word boundary, then whole words, then the remainder
+    let unaligned_head = ptr.align_offset(WORD).min(len);
+    while offset < unaligned_head {
+        core::ptr::write_volatile(ptr.add(offset), byte);
+        offset += 1;
+    }
+    while offset + WORD <= len {
+        core::ptr::write_volatile(ptr.add(offset) as *mut usize, word);
+        offset += WORD;
+    }
+    while offset < len {
+        core::ptr::write_volatile(ptr.add(offset), byte);
+        offset += 1;
+    }
+
+    compiler_fence(Ordering::SeqCst);
+}
@@ -0,0 +1,205 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+use crate::platform::RUNTIME_ALLOCATOR_ID;
+use crate::types::Workload;
+// ========== Workload Hints ==========
+
+/// Hosts at or below this core count count as small for [`Workload::LowFootprint`]
+const SMALL_HOST_CORES: usize = 4;
+
+/// Hosts below this much memory count as small for [`Workload::LowFootprint`]
+const SMALL_HOST_MEMORY: u64 = 4 << 30;
+
+/// Hosts at or below this core count keep the system allocator for [`Workload::LargeBuffers`]
+const FEW_CORES: usize = 2;
+
+/// Hint declared in code, 0 when none
+static DECLARED: AtomicU8 = AtomicU8::new(0);
+
+/// Hint consulted by selection, 0 when none
+static APPLIED: AtomicU8 = AtomicU8::new(0);
+
+const fn encode(workload: Workload) -> u8 {
+    match workload {
+        Workload::ManySmallShortLived => 1,
+        Workload::LargeBuffers => 2,
+        Workload::CrossThreadFree => 3,
+        Workload::LowFootprint => 4,
+        Workload::LatencyCritical => 5,
+    }
+}
+
+const fn decode(value: u8) -> Option<Workload> {
+    match value {
+        1 => Some(Workload::ManySmallShortLived),
+        2 => Some(Workload::LargeBuffers),
+        3 => Some(Workload::CrossThreadFree),
+        4 => Some(Workload::LowFootprint),
+        5 => Some(Workload::LatencyCritical),
+        _ => None,
+    }
+}
+
+/// Name of a workload as accepted by `AUTO_ALLOCATOR_WORKLOAD`
+pub(crate) const fn workload_name(workload: Workload) -> &'static str {
+    match workload {
+        Workload::ManySmallShortLived => "many-small-short-lived",
+        Workload::LargeBuffers => "large-buffers",
+        Workload::CrossThreadFree => "cross-thread-free",
+        Workload::LowFootprint => "low-footprint",
+        Workload::LatencyCritical => "latency-critical",
+    }
+}
+
+/// Reads `AUTO_ALLOCATOR_WORKLOAD`, ignoring unknown values
+fn workload_from_env() -> Option<Workload> {
+    #[cfg(unix)]
+    {
+        crate::platform::with_env_var(c"AUTO_ALLOCATOR_WORKLOAD", |value| match value {
+            b"many-small-short-lived" => Some(Workload::ManySmallShortLived),
+            b"large-buffers" => Some(Workload::LargeBuffers),
+            b"cross-thread-free" => Some(Workload::CrossThreadFree),
+            b"low-footprint" => Some(Workload::LowFootprint),
+            b"latency-critical" => Some(Workload::LatencyCritical),
+            _ => None,
+        })
+        .flatten()
+    }
+
+    #[cfg(not(unix))]
+    None
+}
+
+/// Current hint: the environment variable wins over the one declared in code, so operators can
+/// retarget a binary without rebuilding it
+pub(crate) fn workload_hint() -> Option<Workload> {
+    workload_from_env().or_else(|| decode(DECLARED.load(Ordering::Acquire)))
+}
+
+/// Reads the current hint and records it as the one selection used
+pub(crate) fn apply_hint() -> Option<Workload> {
+    let workload = workload_hint();
+    APPLIED.store(workload.map_or(0, encode), Ordering::Release);
+    workload
+}
+
+/// Hint that decided the selection, if any
+pub(crate) fn applied_hint() -> Option<Workload> {
+    decode(APPLIED.load(Ordering::Acquire))
+}
+
+/// Weighs a workload against the cores and memory the process may use (affinity, cgroup
+/// quota and memory limit applied), returning the allocator ID to use
+///
+/// `mimalloc` is the build the security profile accepts, if one is compiled in.
+pub(crate) fn recommend(workload: Workload, cpu_cores: usize, memory: u64, mimalloc: Option<u8>) -> u8 {
+    let mimalloc = mimalloc.unwrap_or(1);
+
+    match workload {
+        Workload::LowFootprint if cpu_cores <= SMALL_HOST_CORES || memory < SMALL_HOST_MEMORY => 1,
+        Workload::LargeBuffers if cpu_cores <= FEW_CORES => 1,
+        _ => mimalloc,
+    }
+}
+
+/// Tunes mimalloc for the applied hint, called once right after selection
+pub(crate) fn configure_backend(allocator_id: u8) {
+    #[cfg(all(
+        any(feature = "_mimalloc", feature = "_mimalloc_secure"),
+        not(target_arch = "wasm32"),
+        not(debug_assertions)
+    ))]
+    if matches!(allocator_id, 2 | 5) {
+        // mi_option_purge_delay (milliseconds before freed memory is returned to the OS) has no
+        // constant in libmimalloc-sys, which only exports the stable option numbers
+        const MI_OPTION_PURGE_DELAY: libmimalloc_sys::mi_option_t = 15;
+        let purge_delay_ms = match applied_hint() {
+            Some(Workload::LowFootprint) => 0,
+            Some(Workload::LatencyCritical) => 1000,
+            _ => return,
+        };
+        unsafe { libmimalloc_sys::mi_option_set(MI_OPTION_PURGE_DELAY, purge_delay_ms) };
+    }
+
+    let _ = allocator_id;
+}
+
+/// Declares the application's workload for allocator selection
+///
+/// Has an effect only before the first allocation, so call it from a static constructor; the
+/// [`declare_workload!`](crate::declare_workload) macro does exactly that. Returns `false`
+/// when the allocator was already selected and the hint came too late. `AUTO_ALLOCATOR_WORKLOAD`
+/// takes precedence over a hint declared in code.
+///
+/// # Example
+///
+/// ```rust
+/// use auto_allocator::{set_workload, Workload};
+///
+/// // The test harness has allocated already, so the hint is too late here
+/// assert!(!set_workload(Workload::LowFootprint));
+/// ```
+pub fn set_workload(workload: Workload) -> bool {
+    DECLARED.store(encode(workload), Ordering::Release);
+    RUNTIME_ALLOCATOR_ID.load(Ordering::Acquire) == 0
+}
+
+/// Declares the application's workload before `main` and before the first allocation
+///
+/// Expands to a static constructor calling [`set_workload()`](crate::set_workload), placed in
+/// `.init_array` (Linux, Android, BSD, Solaris), `__mod_init_func` (macOS, iOS) or `.CRT$XCU`
+/// (Windows). Use it once, at the top level of the binary crate.
+///
+/// # Example
+///
+/// ```rust
+/// use auto_allocator::{declare_workload, Workload};
+///
+/// // A 2-core gateway would rather keep resident memory small than allocate fastest
+/// declare_workload!(Workload::LowFootprint);
+///
+/// fn main() {
+///     let _info = auto_allocator::get_allocator_info();
+/// }
+/// ```
+#[macro_export]
+macro_rules! declare_workload {
+    ($workload:expr) => {
+        $crate::__static_constructor!(DECLARE_AUTO_ALLOCATOR_WORKLOAD => {
+            $crate::set_workload($workload);
+        });
+    };
+}
+
+/// Runs a block before `main` from a static constructor, shared by [`declare_workload!`] and
+/// the `force-*` backend initialization
+///
+/// The function pointer is placed in `.init_array` (Linux, Android, BSD, Solaris),
+/// `__mod_init_func` (macOS, iOS) or `.CRT$XCU` (Windows).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __static_constructor {
+    ($name:ident => $body:block) => {
+        const _: () = {
+            #[used]
+            #[cfg_attr(
+                any(
+                    target_os = "linux",
+                    target_os = "android",
+                    target_os = "freebsd",
+                    target_os = "netbsd",
+                    target_os = "openbsd",
+                    target_os = "dragonfly",
+                    target_os = "solaris",
+                    target_os = "illumos"
+                ),
+                link_section = ".init_array"
+            )]
+            #[cfg_attr(any(target_os = "macos", target_os = "ios"), link_section = "__DATA,__mod_init_func")]
+            #[cfg_attr(windows, link_section = ".CRT$XCU")]
+            static $name: extern "C" fn() = {
+                extern "C" fn constructor() $body
+                constructor
+            };
+        };
+    };
+}
@@ -0,0 +1,56 @@
+//! Adaptive selection tests for auto-allocator
+//!
+//! The calibration runs on the first allocation of the test binary, so these
+//! tests only inspect what it recorded. Debug builds always use the system
+//! allocator and never calibrate.
+#![cfg(all(feature = "adaptive", unix, not(auto_allocator_forced)))]
+
+use auto_allocator::get_allocator_info;
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_debug_builds_skip_calibration() {
+    let info = get_allocator_info();
+    assert!(info.calibration.is_none());
+    assert!(info.reason.contains("debug"), "{}", info.reason);
+}
+
+#[test]
+#[cfg(not(debug_assertions))]
+fn test_calibration_is_recorded() {
+    let info = get_allocator_info();
+    let calibration = info.calibration.as_ref().expect("calibration did not run");
+
+    assert!(!calibration.timings.is_empty());
+    assert!(calibration.threads >= 2);
+    assert!(calibration.elapsed.as_secs() < 10, "{:?}", calibration.elapsed);
+    assert!(calibration
+        .timings
+        .iter()
+        .any(|timing| timing.allocator_type == auto_allocator::AllocatorType::System));
+}
+
+#[test]
+#[cfg(not(debug_assertions))]
+fn test_fastest_backend_is_selected() {
+    let info = get_allocator_info();
+    let calibration = info.calibration.as_ref().unwrap();
+
+    assert_eq!(calibration.timings[0].allocator_type, info.allocator_type);
+    assert!(calibration
+        .timings
+        .windows(2)
+        .all(|pair| pair[0].total_ns() <= pair[1].total_ns()));
+    assert!(info.reason.contains("adaptive calibration"), "{}", info.reason);
+}
+
+#[test]
+#[cfg(not(debug_assertions))]
+fn test_every_workload_was_measured() {
+    let calibration = get_allocator_info().calibration.clone().unwrap();
+    for timing in &calibration.timings {
+        for ns in [timing.small_ns, timing.large_ns, timing.multi_thread_ns, timing.cross_thread_ns] {
+            assert_ne!(ns, u64::MAX, "{:?}", timing);
+        }
+    }
+}
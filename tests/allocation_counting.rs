@@ -0,0 +1,68 @@
+//! Allocation counting tests for auto-allocator
+//!
+//! These tests verify that `count_allocations` observes exactly the
+//! allocations made by the current thread inside the closure.
+#![cfg(feature = "count-allocations")]
+
+use auto_allocator::{count_allocations, AllocCounts};
+use std::hint::black_box;
+
+#[test]
+fn test_counts_allocations_and_frees() {
+    let counts = count_allocations(|| {
+        let a = black_box(vec![0u8; 100]);
+        let b = black_box(Box::new(42u64));
+        drop(a);
+        drop(b);
+    });
+
+    assert_eq!(counts.allocations, 2);
+    assert_eq!(counts.bytes, 108);
+    assert_eq!(counts.frees, 2);
+}
+
+#[test]
+fn test_no_allocations() {
+    let counts = count_allocations(|| {
+        let mut sum = 0u64;
+        for i in 0..100 {
+            sum += black_box(i);
+        }
+        black_box(sum);
+    });
+
+    assert_eq!(counts, AllocCounts::default());
+}
+
+#[test]
+fn test_nested_counts() {
+    let mut inner = AllocCounts::default();
+    let outer = count_allocations(|| {
+        let _a = black_box(vec![1u8; 16]);
+        inner = count_allocations(|| {
+            let _b = black_box(vec![2u8; 32]);
+        });
+    });
+
+    assert_eq!(inner.allocations, 1);
+    assert_eq!(inner.bytes, 32);
+    assert_eq!(outer.allocations, 2);
+    assert_eq!(outer.bytes, 48);
+    assert_eq!(outer.frees, 2);
+}
+
+#[test]
+fn test_other_threads_are_not_counted() {
+    let counts = count_allocations(|| {
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..10 {
+                    black_box(vec![0u8; 64]);
+                }
+            });
+        });
+    });
+
+    // Spawning allocates on this thread, but the worker's 10 vectors must not show up
+    assert!(counts.bytes < 10 * 64 || counts.allocations < 10);
+}
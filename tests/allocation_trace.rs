@@ -0,0 +1,112 @@
+//! Allocation trace tests for auto-allocator
+//!
+//! These tests record a small workload with start_trace()/stop_trace() and
+//! replay the resulting file against the backends available in the build.
+#![cfg(all(feature = "trace", unix))]
+
+use auto_allocator::{replay_trace, start_trace, stop_trace, AllocatorType};
+use std::hint::black_box;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn record(name: &str, workload: impl FnOnce()) -> (PathBuf, auto_allocator::TraceSummary) {
+    let path = std::env::temp_dir().join(format!("auto-allocator-{}-{}.trace", std::process::id(), name));
+    start_trace(&path).unwrap();
+    workload();
+    let summary = stop_trace().unwrap();
+    (path, summary)
+}
+
+fn growing_vectors() {
+    let mut blocks = Vec::new();
+    for i in 0..64 {
+        let mut block = black_box(Vec::with_capacity(1024));
+        block.resize(4096 + i, i as u8);
+        blocks.push(block);
+    }
+    black_box(&blocks);
+}
+
+#[test]
+fn test_trace_records_events() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let (path, summary) = record("records", growing_vectors);
+
+    // 64 allocations, 64 reallocations and their frees, plus the outer vector
+    assert!(summary.events >= 64 * 3, "{:?}", summary);
+    let bytes = std::fs::read(&path).unwrap();
+    assert_eq!(bytes.len() as u64, summary.bytes);
+    assert!(bytes.starts_with(b"AATRACE"));
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_stop_without_trace_is_empty() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    assert_eq!(stop_trace().unwrap(), auto_allocator::TraceSummary::default());
+}
+
+#[test]
+fn test_replay_reports_workload() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let (path, summary) = record("replay", growing_vectors);
+
+    let report = replay_trace(&path, AllocatorType::System).unwrap();
+    assert_eq!(report.allocator_type, AllocatorType::System);
+    assert_eq!(report.events, summary.events);
+    assert!(report.peak_live_bytes >= 64 * 4096, "{:?}", report);
+    assert!(report.peak_rss_bytes > 0);
+    assert!((0.0..=1.0).contains(&report.fragmentation));
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+#[cfg(not(debug_assertions))]
+fn test_replay_against_mimalloc() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let (path, summary) = record("mimalloc", growing_vectors);
+
+    for allocator_type in [AllocatorType::Mimalloc, AllocatorType::MimallocSecure] {
+        match replay_trace(&path, allocator_type) {
+            Ok(report) => assert_eq!(report.events, summary.events),
+            Err(err) => assert_eq!(err.kind(), std::io::ErrorKind::Unsupported),
+        }
+    }
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_replay_rejects_unavailable_backend() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let (path, _) = record("unsupported", growing_vectors);
+
+    let err = replay_trace(&path, AllocatorType::EmbeddedHeap).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_replay_rejects_other_files() {
+    let path = std::env::temp_dir().join(format!("auto-allocator-{}-garbage.trace", std::process::id()));
+    std::fs::write(&path, b"not a trace file").unwrap();
+
+    let err = replay_trace(&path, AllocatorType::System).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_replay_rejects_out_of_range_ids() {
+    let path = std::env::temp_dir().join(format!("auto-allocator-{}-corrupt.trace", std::process::id()));
+    // Header, then an allocation of object 2^62 on thread 0 at time 0
+    let mut trace = b"AATRACE\x01\x00\x00\x00".to_vec();
+    trace.extend_from_slice(&[0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x40]);
+    trace.extend_from_slice(&[0x10, 0x03]);
+    std::fs::write(&path, trace).unwrap();
+
+    let err = replay_trace(&path, AllocatorType::System).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,58 @@
+//! Direct backend access tests for auto-allocator
+//!
+//! These tests use each compiled-in backend through BackendAllocator,
+//! independently of the allocator selected for the process.
+#![cfg(not(target_os = "none"))]
+
+use auto_allocator::{AllocatorType, BackendAllocator};
+use std::alloc::{GlobalAlloc, Layout};
+
+#[test]
+fn test_system_backend_comes_first() {
+    let backends = BackendAllocator::available();
+    assert_eq!(backends[0].allocator_type(), AllocatorType::System);
+    assert_eq!(backends[0].name(), "system");
+    assert_eq!(BackendAllocator::new(AllocatorType::EmbeddedHeap), None);
+}
+
+#[test]
+fn test_every_backend_allocates() {
+    for backend in BackendAllocator::available() {
+        for (size, align) in [(1, 1), (64, 8), (4096, 64), (1 << 20, 16)] {
+            let layout = Layout::from_size_align(size, align).unwrap();
+            unsafe {
+                let ptr = backend.alloc(layout);
+                assert!(!ptr.is_null(), "{} failed {:?}", backend.name(), layout);
+                assert_eq!(ptr as usize % align, 0);
+                ptr.write_bytes(0xAB, size);
+
+                let grown = backend.realloc(ptr, layout, size * 2);
+                assert!(!grown.is_null());
+                assert_eq!(*grown.add(size - 1), 0xAB);
+                backend.dealloc(grown, Layout::from_size_align(size * 2, align).unwrap());
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg(all(
+    not(debug_assertions),
+    any(target_os = "linux", target_os = "macos", target_os = "windows")
+))]
+fn test_mimalloc_build_is_available() {
+    let mimalloc: Vec<_> = BackendAllocator::available()
+        .into_iter()
+        .filter(|backend| {
+            matches!(backend.allocator_type(), AllocatorType::Mimalloc | AllocatorType::MimallocSecure)
+        })
+        .collect();
+    // Exactly one mimalloc build is compiled in by default or with `secure`
+    assert_eq!(mimalloc.len(), 1, "{:?}", mimalloc);
+}
+
+#[test]
+#[cfg(not(all(feature = "debug-guard", unix)))]
+fn test_debug_guard_needs_its_feature() {
+    assert_eq!(BackendAllocator::new(AllocatorType::DebugGuard), None);
+}
@@ -0,0 +1,90 @@
+//! Canary redzone tests for auto-allocator
+//!
+//! Tests smash canaries on purpose and restore them before freeing; a smashed
+//! canary found on free aborts, so that scenario runs in a child process.
+#![cfg(feature = "canary")]
+
+use auto_allocator::{verify_heap, CanaryRegion};
+use std::alloc::{alloc, dealloc, Layout};
+use std::hint::black_box;
+use std::process::Command;
+use std::sync::Mutex;
+
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[test]
+fn test_intact_heap_verifies() {
+    let _serial = SERIAL.lock().unwrap();
+    let blocks: Vec<Vec<u8>> = (0..32).map(|i| vec![i as u8; i * 7 + 1]).collect();
+    let check = verify_heap().unwrap();
+    assert!(check.blocks >= blocks.len());
+    assert!(check.bytes >= blocks.iter().map(Vec::len).sum::<usize>());
+}
+
+#[test]
+fn test_overflow_is_found_by_verify_heap() {
+    let _serial = SERIAL.lock().unwrap();
+    let layout = Layout::from_size_align(32, 8).unwrap();
+    unsafe {
+        let ptr = black_box(alloc(layout));
+        let saved = ptr.add(32).read_volatile();
+        ptr.add(32).write_volatile(0);
+
+        let corruption = verify_heap().unwrap_err();
+        assert_eq!(corruption.address, ptr as usize);
+        assert_eq!((corruption.size, corruption.align), (32, 8));
+        assert_eq!(corruption.region, CanaryRegion::Tail);
+        assert_eq!(corruption.offset, 0);
+
+        ptr.add(32).write_volatile(saved);
+        assert!(verify_heap().is_ok());
+        dealloc(ptr, layout);
+    }
+}
+
+#[test]
+fn test_underflow_is_found_by_verify_heap() {
+    let _serial = SERIAL.lock().unwrap();
+    let layout = Layout::from_size_align(100, 128).unwrap();
+    unsafe {
+        let ptr = black_box(alloc(layout));
+        assert_eq!(ptr as usize % 128, 0);
+        let saved = ptr.sub(3).read_volatile();
+        ptr.sub(3).write_volatile(0);
+
+        let corruption = verify_heap().unwrap_err();
+        assert_eq!(corruption.region, CanaryRegion::Head);
+        assert_eq!(corruption.offset, 2);
+
+        ptr.sub(3).write_volatile(saved);
+        dealloc(ptr, layout);
+    }
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn child_overflow_then_free() {
+    if std::env::var_os("CANARY_TEST_CHILD").is_none() {
+        return;
+    }
+    let mut buffer = vec![0u8; 24];
+    unsafe { black_box(buffer.as_mut_ptr()).add(25).write_volatile(1) };
+    drop(buffer);
+}
+
+#[test]
+fn test_smashed_canary_aborts_on_free() {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["child_overflow_then_free", "--exact", "--ignored", "--test-threads=1"])
+        .env("CANARY_TEST_CHILD", "1")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains("heap canary smashed after block") && stderr.contains("(size 24, align 1)"),
+        "stderr: {}",
+        stderr
+    );
+}
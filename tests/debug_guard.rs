@@ -0,0 +1,83 @@
+//! Guard-page backend tests for auto-allocator
+//!
+//! The backend is chosen before the first allocation, so each scenario re-runs
+//! this test binary as a child process with `AUTO_ALLOCATOR_BACKEND=debug-guard`.
+#![cfg(all(feature = "debug-guard", unix, not(auto_allocator_forced)))]
+
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, ExitStatus};
+
+fn run_child(test_name: &str, mode: &str) -> ExitStatus {
+    Command::new(std::env::current_exe().unwrap())
+        .args([test_name, "--exact", "--ignored", "--test-threads=1"])
+        .env("AUTO_ALLOCATOR_BACKEND", "debug-guard")
+        .env("AUTO_ALLOCATOR_GUARD_MODE", mode)
+        .status()
+        .unwrap()
+}
+
+fn in_guarded_child() -> bool {
+    std::env::var("AUTO_ALLOCATOR_BACKEND").as_deref() == Ok("debug-guard")
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn child_in_bounds_use() {
+    if !in_guarded_child() {
+        return;
+    }
+    assert_eq!(auto_allocator::get_allocator_type(), auto_allocator::AllocatorType::DebugGuard);
+    assert!(auto_allocator::get_allocator_info().reason.contains("AUTO_ALLOCATOR_BACKEND"));
+
+    let mut values: Vec<u64> = (0..10_000).collect();
+    values.retain(|v| v % 3 == 0);
+    let text: String = values.iter().take(100).map(|v| v.to_string()).collect();
+    let aligned = std::alloc::Layout::from_size_align(100, 64).unwrap();
+    unsafe {
+        let ptr = std::alloc::alloc(aligned);
+        assert_eq!(ptr as usize % 64, 0);
+        std::ptr::write_bytes(ptr, 0xAB, 100);
+        std::alloc::dealloc(ptr, aligned);
+    }
+    assert!(text.starts_with("0369"));
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn child_overflow() {
+    if !in_guarded_child() {
+        return;
+    }
+    assert_eq!(auto_allocator::guard_mode(), auto_allocator::GuardMode::Overflow);
+    let mut buffer = vec![0u8; 100];
+    unsafe { std::ptr::write_volatile(buffer.as_mut_ptr().add(100), 1) };
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn child_underflow() {
+    if !in_guarded_child() {
+        return;
+    }
+    assert_eq!(auto_allocator::guard_mode(), auto_allocator::GuardMode::Underflow);
+    let mut buffer = vec![0u8; 100];
+    unsafe { std::ptr::write_volatile(buffer.as_mut_ptr().sub(1), 1) };
+}
+
+#[test]
+fn test_guarded_allocations_work() {
+    let status = run_child("child_in_bounds_use", "overflow");
+    assert!(status.success(), "child failed: {:?}", status);
+}
+
+#[test]
+fn test_overflow_hits_guard_page() {
+    let status = run_child("child_overflow", "overflow");
+    assert!(status.signal().is_some(), "expected a fault, got {:?}", status);
+}
+
+#[test]
+fn test_underflow_hits_guard_page() {
+    let status = run_child("child_underflow", "underflow");
+    assert!(status.signal().is_some(), "expected a fault, got {:?}", status);
+}
@@ -0,0 +1,61 @@
+//! Debugging tool detection tests for auto-allocator
+//!
+//! A sanitizer runtime named in LD_PRELOAD is enough for detection, so the
+//! child process preloads a name that does not exist; the loader ignores it.
+#![cfg(all(not(target_os = "none"), not(auto_allocator_forced)))]
+
+use auto_allocator::{get_allocator_info, get_recommended_allocator, AllocatorType, DebuggingTool};
+
+#[test]
+fn test_detected_tool_forces_system_allocator() {
+    let info = get_allocator_info();
+    match info.system_info.debugging_tool {
+        // Running the suite itself under Valgrind or a sanitizer
+        Some(_) => {
+            assert_eq!(info.allocator_type, AllocatorType::System);
+            assert!(info.reason.contains("runs under"), "{}", info.reason);
+        }
+        None => assert!(!info.reason.contains("runs under"), "{}", info.reason),
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod preload {
+    use super::*;
+    use std::process::Command;
+
+    const CHILD_MARKER: &str = "AUTO_ALLOCATOR_DEBUGGING_TEST_CHILD";
+
+    #[test]
+    #[ignore = "runs in a child process"]
+    fn sanitizer_child() {
+        if std::env::var_os(CHILD_MARKER).is_none() {
+            return;
+        }
+        let info = get_allocator_info();
+        assert_eq!(info.system_info.debugging_tool, Some(DebuggingTool::AddressSanitizer));
+        assert_eq!(info.allocator_type, AllocatorType::System);
+        assert!(info.reason.contains("runs under AddressSanitizer"), "{}", info.reason);
+
+        let (recommended, reason) = get_recommended_allocator();
+        assert_eq!(recommended, AllocatorType::System);
+        assert!(reason.contains("runs under AddressSanitizer"), "{}", reason);
+    }
+
+    #[test]
+    fn test_preloaded_sanitizer_is_detected() {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args(["preload::sanitizer_child", "--exact", "--ignored", "--test-threads=1"])
+            .env(CHILD_MARKER, "1")
+            .env("LD_PRELOAD", "libasan.so.auto-allocator-test")
+            .env("AUTO_ALLOCATOR_CACHE", "off")
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Fault injection tests for auto-allocator
+//!
+//! These tests use thread-scoped schedules so the test harness itself
+//! never sees injected failures.
+#![cfg(feature = "fault-injection")]
+
+use auto_allocator::{inject_failures, with_injected_failures, FailureSchedule};
+
+fn try_alloc(size: usize) -> bool {
+    let mut buffer: Vec<u8> = Vec::new();
+    buffer.try_reserve_exact(size).is_ok()
+}
+
+#[test]
+fn test_nth_allocation_fails_once() {
+    let results: Vec<bool> = {
+        let mut results = Vec::with_capacity(5);
+        let _guard = inject_failures(FailureSchedule::Nth(3));
+        for _ in 0..5 {
+            results.push(try_alloc(16));
+        }
+        results
+    };
+    assert_eq!(results, [true, true, false, true, true]);
+}
+
+#[test]
+fn test_larger_than_threshold_fails() {
+    with_injected_failures(FailureSchedule::LargerThan(1024), || {
+        assert!(try_alloc(1024));
+        assert!(!try_alloc(1025));
+        assert!(try_alloc(8));
+    });
+}
+
+#[test]
+fn test_random_schedule_is_reproducible() {
+    let run = |seed| {
+        let mut outcomes = Vec::with_capacity(64);
+        let _guard = inject_failures(FailureSchedule::Random { probability: 0.5, seed });
+        for _ in 0..64 {
+            outcomes.push(try_alloc(32));
+        }
+        outcomes
+    };
+
+    let first = run(42);
+    let second = run(42);
+    assert_eq!(first, second);
+    assert!(first.iter().any(|&ok| ok));
+    assert!(first.iter().any(|&ok| !ok));
+}
+
+#[test]
+fn test_scope_ends_on_drop() {
+    {
+        let _guard = inject_failures(FailureSchedule::LargerThan(0));
+        assert!(!try_alloc(1));
+    }
+    assert!(try_alloc(1));
+}
+
+#[test]
+fn test_failures_are_counted() {
+    let before = auto_allocator::injected_failure_count();
+    with_injected_failures(FailureSchedule::LargerThan(0), || {
+        assert!(!try_alloc(64));
+    });
+    assert!(auto_allocator::injected_failure_count() > before);
+}
@@ -0,0 +1,86 @@
+//! Allocation-forbidden region tests for auto-allocator
+//!
+//! These tests run in `Count` mode so violations can be observed without
+//! aborting the test process.
+#![cfg(feature = "forbid-allocations")]
+
+use auto_allocator::{
+    deny_allocations, forbid_allocations, forbidden_allocation_count,
+    set_forbidden_allocation_action, ForbiddenAllocationAction,
+};
+use std::hint::black_box;
+use std::sync::Mutex;
+
+// Violation counts are process-wide, so tests that measure them must not overlap
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[test]
+fn test_allocation_in_forbidden_region_is_counted() {
+    let _serial = SERIAL.lock().unwrap();
+    set_forbidden_allocation_action(ForbiddenAllocationAction::Count);
+
+    let before = forbidden_allocation_count();
+    forbid_allocations(|| {
+        black_box(vec![0u8; 32]);
+    });
+    assert_eq!(forbidden_allocation_count() - before, 1);
+}
+
+#[test]
+fn test_allocation_free_region_is_clean() {
+    let _serial = SERIAL.lock().unwrap();
+    set_forbidden_allocation_action(ForbiddenAllocationAction::Count);
+
+    let data = [1u32, 2, 3, 4];
+    let before = forbidden_allocation_count();
+    let sum: u32 = forbid_allocations(|| data.iter().sum());
+    assert_eq!(sum, 10);
+    assert_eq!(forbidden_allocation_count(), before);
+}
+
+#[test]
+fn test_guard_scope_ends_on_drop() {
+    let _serial = SERIAL.lock().unwrap();
+    set_forbidden_allocation_action(ForbiddenAllocationAction::Count);
+
+    let before = forbidden_allocation_count();
+    {
+        let _outer = deny_allocations();
+        let _inner = deny_allocations();
+    }
+    black_box(vec![0u8; 32]);
+    assert_eq!(forbidden_allocation_count(), before);
+}
+
+#[test]
+fn test_other_threads_may_allocate() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let _serial = SERIAL.lock().unwrap();
+    set_forbidden_allocation_action(ForbiddenAllocationAction::Count);
+
+    let start = Arc::new(AtomicBool::new(false));
+    let done = Arc::new(AtomicBool::new(false));
+    let worker = {
+        let (start, done) = (Arc::clone(&start), Arc::clone(&done));
+        std::thread::spawn(move || {
+            while !start.load(Ordering::Acquire) {
+                std::hint::spin_loop();
+            }
+            black_box(vec![0u8; 64]);
+            done.store(true, Ordering::Release);
+        })
+    };
+
+    let before = forbidden_allocation_count();
+    forbid_allocations(|| {
+        // The worker allocates while this thread is inside its forbidden region
+        start.store(true, Ordering::Release);
+        while !done.load(Ordering::Acquire) {
+            std::hint::spin_loop();
+        }
+    });
+    worker.join().unwrap();
+    assert_eq!(forbidden_allocation_count(), before);
+}
@@ -0,0 +1,61 @@
+//! Compile-time forced backend tests for auto-allocator
+//!
+//! Only built with a force-* feature, e.g.
+//! `cargo test --release --features force-mimalloc --test forced_backend`.
+#![cfg(all(auto_allocator_forced, not(target_os = "none")))]
+
+use auto_allocator::{get_allocator_info, get_recommended_allocator, AllocatorType};
+use std::process::Command;
+
+const CHILD_MARKER: &str = "AUTO_ALLOCATOR_FORCED_TEST_CHILD";
+
+/// Backend the enabled features force: mimalloc is only compiled into release builds
+fn forced_type() -> AllocatorType {
+    if cfg!(any(feature = "force-system", debug_assertions)) {
+        AllocatorType::System
+    } else if cfg!(feature = "_mimalloc_secure") {
+        AllocatorType::MimallocSecure
+    } else {
+        AllocatorType::Mimalloc
+    }
+}
+
+#[test]
+fn test_forced_backend_is_selected() {
+    let info = get_allocator_info();
+    assert_eq!(info.allocator_type, forced_type());
+    assert!(info.reason.contains("forced at compile time by the force-"), "{}", info.reason);
+
+    let (recommended, reason) = get_recommended_allocator();
+    assert_eq!(recommended, forced_type());
+    assert!(reason.contains("feature"), "{}", reason);
+}
+
+#[test]
+fn test_forced_backend_serves_allocations() {
+    let blocks: Vec<Vec<u8>> = (0..64).map(|i| vec![i as u8; 16 << (i % 12)]).collect();
+    for (i, block) in blocks.iter().enumerate() {
+        assert!(block.iter().all(|&b| b == i as u8));
+    }
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn override_child() {
+    if std::env::var_os(CHILD_MARKER).is_none() {
+        return;
+    }
+    assert_eq!(get_allocator_info().allocator_type, forced_type());
+}
+
+#[test]
+fn test_override_cannot_replace_forced_backend() {
+    let other = if forced_type() == AllocatorType::System { "mimalloc" } else { "system" };
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args(["override_child", "--exact", "--ignored", "--test-threads=1"])
+        .env(CHILD_MARKER, "1")
+        .env("AUTO_ALLOCATOR_BACKEND", other)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
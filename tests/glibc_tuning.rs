@@ -0,0 +1,86 @@
+//! glibc malloc tuning tests for auto-allocator
+//!
+//! Each case pins the system allocator with AUTO_ALLOCATOR_BACKEND and runs in a
+//! child process, so mallopt sees the environment under test at selection.
+#![cfg(all(target_os = "linux", target_env = "gnu", not(auto_allocator_forced)))]
+
+use auto_allocator::{get_allocator_info, AllocatorType, GlibcTuning};
+use std::process::Command;
+
+const CHILD_MARKER: &str = "AUTO_ALLOCATOR_GLIBC_TEST_CHILD";
+
+fn run_child(name: &str, env: &[(&str, &str)]) {
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args([name, "--exact", "--ignored", "--test-threads=1"])
+        .env(CHILD_MARKER, "1")
+        .env("AUTO_ALLOCATOR_BACKEND", "system")
+        .env_remove("MALLOC_ARENA_MAX")
+        .env_remove("MALLOC_MMAP_THRESHOLD_")
+        .env_remove("MALLOC_TRIM_THRESHOLD_")
+        .env_remove("GLIBC_TUNABLES")
+        .envs(env.iter().copied())
+        .status()
+        .unwrap();
+    assert!(status.success(), "{} failed", name);
+}
+
+fn child_tuning() -> Option<Option<GlibcTuning>> {
+    std::env::var_os(CHILD_MARKER)?;
+    let info = get_allocator_info();
+    assert_eq!(info.allocator_type, AllocatorType::System);
+    Some(info.glibc_tuning)
+}
+
+#[test]
+fn test_tuning_only_for_system_allocator() {
+    let info = get_allocator_info();
+    if info.allocator_type != AllocatorType::System {
+        assert_eq!(info.glibc_tuning, None);
+    }
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn defaults_child() {
+    let Some(tuning) = child_tuning() else { return };
+    let tuning = tuning.expect("system allocator on glibc is tuned");
+    assert!(tuning.arena_max.unwrap() >= 2);
+    assert!(tuning.arena_max.unwrap() <= get_allocator_info().system_info.cpu_cores.max(2));
+    assert!(matches!(tuning.mmap_threshold, Some(131072 | 262144)), "{:?}", tuning);
+    assert!(tuning.trim_threshold.unwrap() > tuning.mmap_threshold.unwrap());
+}
+
+#[test]
+fn test_system_allocator_is_tuned() {
+    run_child("defaults_child", &[]);
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn disabled_child() {
+    let Some(tuning) = child_tuning() else { return };
+    assert_eq!(tuning, None);
+}
+
+#[test]
+fn test_tuning_can_be_turned_off() {
+    run_child("disabled_child", &[("AUTO_ALLOCATOR_GLIBC_TUNING", "off")]);
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn environment_child() {
+    let Some(tuning) = child_tuning() else { return };
+    let tuning = tuning.expect("other parameters are still tuned");
+    assert_eq!(tuning.arena_max, None);
+    assert_eq!(tuning.mmap_threshold, None);
+    assert!(tuning.trim_threshold.is_some());
+}
+
+#[test]
+fn test_environment_settings_are_left_alone() {
+    run_child(
+        "environment_child",
+        &[("MALLOC_ARENA_MAX", "3"), ("GLIBC_TUNABLES", "glibc.malloc.mmap_threshold=65536")],
+    );
+}
@@ -0,0 +1,63 @@
+//! Huge page detection and policy tests for auto-allocator
+//!
+//! Detection is compared against sysfs directly; the `madvise` policy is checked
+//! through the `hg` flag the kernel shows for advised mappings in smaps.
+
+use auto_allocator::{get_allocator_info, TransparentHugePages};
+
+#[test]
+fn test_transparent_huge_pages_reported() {
+    let reported = get_allocator_info().system_info.transparent_huge_pages;
+    let expected = match std::fs::read_to_string("/sys/kernel/mm/transparent_hugepage/enabled") {
+        Ok(text) if text.contains("[always]") => TransparentHugePages::Always,
+        Ok(text) if text.contains("[madvise]") => TransparentHugePages::Madvise,
+        Ok(text) if text.contains("[never]") => TransparentHugePages::Never,
+        _ => TransparentHugePages::Unsupported,
+    };
+    assert_eq!(reported, expected);
+}
+
+#[test]
+fn test_huge_page_sizes_reported() {
+    let sizes = &get_allocator_info().system_info.huge_page_sizes;
+    assert!(sizes.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", sizes);
+    assert!(sizes.iter().all(|size| size.is_power_of_two() && *size > 4096), "{:?}", sizes);
+    if !cfg!(target_os = "linux") {
+        assert!(sizes.is_empty());
+    }
+}
+
+/// Returns the `VmFlags` line of the smaps entry containing `addr`
+#[cfg(all(feature = "huge-pages", target_os = "linux"))]
+fn vm_flags(addr: usize) -> Option<String> {
+    let smaps = std::fs::read_to_string("/proc/self/smaps").ok()?;
+    let mut inside = false;
+    for line in smaps.lines() {
+        if let Some((range, _)) = line.split_once(' ') {
+            if let Some((start, end)) = range.split_once('-') {
+                if let (Ok(start), Ok(end)) =
+                    (usize::from_str_radix(start, 16), usize::from_str_radix(end, 16))
+                {
+                    inside = (start..end).contains(&addr);
+                    continue;
+                }
+            }
+        }
+        if inside && line.starts_with("VmFlags:") {
+            return Some(line.to_string());
+        }
+    }
+    None
+}
+
+#[test]
+#[cfg(all(feature = "huge-pages", target_os = "linux"))]
+fn test_large_allocations_are_advised() {
+    if get_allocator_info().system_info.transparent_huge_pages != TransparentHugePages::Madvise {
+        return;
+    }
+    let buffer = vec![1u8; 8 * 1024 * 1024];
+    let aligned = buffer.as_ptr() as usize + buffer.as_ptr().align_offset(2 * 1024 * 1024);
+    let flags = vm_flags(aligned).expect("buffer not found in smaps");
+    assert!(flags.split_whitespace().any(|flag| flag == "hg"), "{}", flags);
+}
@@ -0,0 +1,79 @@
+//! Leak report tests for auto-allocator
+//!
+//! These tests verify that allocations still live when the report is written
+//! show up in the DHAT-compatible JSON output.
+#![cfg(feature = "leak-report")]
+
+#[test]
+fn test_leaked_allocation_is_reported() {
+    let dir = std::env::temp_dir();
+
+    // black_box keeps release builds from eliding the unused allocation
+    let leaked: &'static mut [u8] =
+        std::hint::black_box(Box::leak(vec![7u8; 4096].into_boxed_slice()));
+    let path = dir.join("auto_allocator_test_leaks.json");
+    let summary = auto_allocator::write_leak_report(&path).unwrap();
+
+    assert!(summary.sites >= 1);
+    assert!(summary.blocks >= 1);
+    assert!(summary.bytes >= leaked.len() as u64);
+
+    let json = std::fs::read_to_string(&path).unwrap();
+    assert!(json.contains("\"dhatFileVersion\":2"));
+    assert!(json.contains("\"mode\":\"rust-heap\""));
+    assert!(json.contains("\"eb\":"));
+    assert!(json.contains("\"[root]\""));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_freed_allocation_is_not_reported() {
+    let dir = std::env::temp_dir();
+    let before = auto_allocator::write_leak_report(dir.join("auto_allocator_before.json")).unwrap();
+
+    let data: Vec<u8> = std::hint::black_box(vec![1; 1 << 20]);
+    drop(data);
+
+    let after = auto_allocator::write_leak_report(dir.join("auto_allocator_after.json")).unwrap();
+    // Anything freed in between must not contribute a 1MB live block
+    assert!(after.bytes < before.bytes + (1 << 20));
+    let _ = std::fs::remove_file(dir.join("auto_allocator_before.json"));
+    let _ = std::fs::remove_file(dir.join("auto_allocator_after.json"));
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn child_exits() {}
+
+#[test]
+fn test_requested_report_is_written_at_exit() {
+    let path = std::env::temp_dir().join(format!("auto_allocator_exit_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let status = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["child_exits", "--exact", "--ignored", "--test-threads=1"])
+        .env("AUTO_ALLOCATOR_LEAK_REPORT", &path)
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+    let json = std::fs::read_to_string(&path).unwrap();
+    assert!(json.contains("\"dhatFileVersion\":2"));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn test_no_report_without_a_path() {
+    let dir = std::env::temp_dir().join(format!("auto_allocator_no_report_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let status = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["child_exits", "--exact", "--ignored", "--test-threads=1"])
+        .env_remove("AUTO_ALLOCATOR_LEAK_REPORT")
+        .current_dir(&dir)
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+    // std's statics are live at exit, yet nothing may land in the working directory
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,55 @@
+//! C library detection tests for auto-allocator
+//!
+//! glibc is detected at runtime, musl and bionic from the target. On musl the
+//! crate prefers mimalloc whatever the core count, which release builds check.
+#![cfg(not(target_os = "none"))]
+
+use auto_allocator::{get_allocator_info, LibcFlavor};
+
+#[test]
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn test_glibc_version_is_detected() {
+    match get_allocator_info().system_info.libc_flavor {
+        LibcFlavor::Glibc { major, minor } => {
+            assert_eq!(major, 2);
+            assert!(minor > 0);
+        }
+        other => panic!("expected glibc, got {:?}", other),
+    }
+}
+
+#[test]
+#[cfg(target_env = "musl")]
+fn test_musl_is_detected() {
+    assert_eq!(get_allocator_info().system_info.libc_flavor, LibcFlavor::Musl);
+}
+
+#[test]
+#[cfg(all(
+    target_env = "musl",
+    not(debug_assertions),
+    any(feature = "_mimalloc", feature = "_mimalloc_secure")
+))]
+fn test_musl_prefers_mimalloc_on_any_core_count() {
+    use auto_allocator::AllocatorType;
+
+    let info = get_allocator_info();
+    if info.allocator_type == AllocatorType::System {
+        // Only a low-memory host, a hint or an override keeps musl malloc
+        assert!(!info.reason.contains("runtime fallback"), "{}", info.reason);
+    } else if info.workload.is_none() && info.calibration.is_none() {
+        assert!(info.reason.contains("musl"), "{}", info.reason);
+    }
+}
+
+#[test]
+#[cfg(target_os = "android")]
+fn test_bionic_is_detected() {
+    assert_eq!(get_allocator_info().system_info.libc_flavor, LibcFlavor::Bionic);
+}
+
+#[test]
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn test_other_platforms_report_other() {
+    assert_eq!(get_allocator_info().system_info.libc_flavor, LibcFlavor::Other);
+}
@@ -0,0 +1,59 @@
+//! Locked memory tests for auto-allocator
+//!
+//! Counters are process-wide, so tests that read them run one at a time.
+#![cfg(all(feature = "locked-memory", unix))]
+
+use allocator_api2::vec::Vec;
+use auto_allocator::{locked_memory_status, LockedAllocator};
+use std::sync::Mutex;
+
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[test]
+fn test_locked_vec_is_tracked_and_released() {
+    let _serial = SERIAL.lock().unwrap();
+    let before = locked_memory_status();
+
+    let mut secret: Vec<u8, LockedAllocator> = Vec::new_in(LockedAllocator);
+    secret.extend_from_slice(b"hunter2");
+    assert_eq!(secret.as_slice(), b"hunter2");
+
+    let during = locked_memory_status();
+    assert_eq!(during.allocations, before.allocations + 1);
+    assert!(during.locked_bytes > before.locked_bytes);
+
+    drop(secret);
+    assert_eq!(locked_memory_status(), before);
+}
+
+#[test]
+fn test_growth_stays_within_locked_pages() {
+    let _serial = SERIAL.lock().unwrap();
+    let before = locked_memory_status();
+
+    let mut buffer: Vec<u64, LockedAllocator> = Vec::with_capacity_in(4, LockedAllocator);
+    buffer.extend(0..1000);
+    assert_eq!(buffer.iter().sum::<u64>(), 999 * 1000 / 2);
+    assert_eq!(locked_memory_status().allocations, before.allocations + 1);
+
+    drop(buffer);
+    assert_eq!(locked_memory_status().locked_bytes, before.locked_bytes);
+}
+
+#[test]
+fn test_zero_sized_allocations_lock_nothing() {
+    let _serial = SERIAL.lock().unwrap();
+    let before = locked_memory_status();
+    let empty: Vec<u8, LockedAllocator> = Vec::new_in(LockedAllocator);
+    assert_eq!(empty.capacity(), 0);
+    assert_eq!(locked_memory_status(), before);
+}
+
+#[test]
+fn test_headroom_reflects_limit() {
+    let status = locked_memory_status();
+    match status.limit_bytes {
+        Some(limit) => assert_eq!(status.headroom_bytes(), Some(limit.saturating_sub(status.locked_bytes))),
+        None => assert_eq!(status.headroom_bytes(), None),
+    }
+}
@@ -0,0 +1,159 @@
+//! Low-memory host policy tests for auto-allocator
+//!
+//! The thresholds are moved above this machine's memory with environment
+//! variables, each case in a child process so it selects afresh. Debug builds
+//! select at compile time and never consult the memory class.
+#![cfg(all(not(target_os = "none"), not(auto_allocator_forced)))]
+
+use auto_allocator::get_allocator_info;
+
+#[test]
+fn test_effective_memory_is_capped_by_total() {
+    let system_info = &get_allocator_info().system_info;
+    assert!(system_info.effective_memory_bytes > 0);
+    assert!(system_info.effective_memory_bytes <= system_info.total_memory_bytes);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_effective_memory_follows_own_cgroup() {
+    // The limit of the cgroup this process runs in, not the root's, caps the effective memory
+    let membership = std::fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+    let limit = membership.lines().find_map(|line| {
+        let mut fields = line.splitn(3, ':');
+        let (id, controllers, path) = (fields.next()?, fields.next()?, fields.next()?);
+        let file = if id == "0" && controllers.is_empty() {
+            format!("/sys/fs/cgroup{}/memory.max", path)
+        } else if controllers.split(',').any(|name| name == "memory") {
+            format!("/sys/fs/cgroup/memory{}/memory.limit_in_bytes", path)
+        } else {
+            return None;
+        };
+        std::fs::read_to_string(file).ok()?.trim().parse::<u64>().ok()
+    });
+    if let Some(limit) = limit {
+        assert!(get_allocator_info().system_info.effective_memory_bytes <= limit);
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_debug_builds_ignore_memory_class() {
+    let info = get_allocator_info();
+    assert!(info.reason.contains("debug"), "{}", info.reason);
+    assert!(!info.reason.contains("effective memory"), "{}", info.reason);
+}
+
+#[cfg(all(not(debug_assertions), unix))]
+mod release {
+    use super::*;
+    use auto_allocator::{get_recommended_allocator, AllocatorType};
+    use std::process::Command;
+
+    const CHILD_MARKER: &str = "AUTO_ALLOCATOR_LOW_MEMORY_TEST_CHILD";
+
+    fn run_child(name: &str, env: &[(&str, &str)]) {
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args([name, "--exact", "--ignored", "--test-threads=1"])
+            .env(CHILD_MARKER, "1")
+            .env("AUTO_ALLOCATOR_CACHE", "off")
+            .envs(env.iter().copied())
+            .status()
+            .unwrap();
+        assert!(status.success(), "{} failed", name);
+    }
+
+    fn is_mimalloc(allocator_type: AllocatorType) -> bool {
+        matches!(allocator_type, AllocatorType::Mimalloc | AllocatorType::MimallocSecure)
+    }
+
+    #[test]
+    #[ignore = "runs in a child process"]
+    fn low_memory_child() {
+        if std::env::var_os(CHILD_MARKER).is_none() {
+            return;
+        }
+        let info = get_allocator_info();
+        assert_eq!(info.allocator_type, AllocatorType::System);
+        assert!(info.reason.contains("low-memory host"), "{}", info.reason);
+        assert!(info.reason.contains("1TB low-memory threshold"), "{}", info.reason);
+
+        let (recommended, reason) = get_recommended_allocator();
+        assert_eq!(recommended, AllocatorType::System);
+        assert!(reason.contains("effective memory, below the 1TB"), "{}", reason);
+    }
+
+    #[test]
+    fn test_low_memory_host_selects_system() {
+        run_child("release::low_memory_child", &[("AUTO_ALLOCATOR_LOW_MEMORY", "1024G")]);
+    }
+
+    #[test]
+    #[ignore = "runs in a child process"]
+    fn constrained_memory_child() {
+        if std::env::var_os(CHILD_MARKER).is_none() {
+            return;
+        }
+        let info = get_allocator_info();
+        let multi_threaded = info.system_info.cpu_cores >= 2 || info.system_info.numa_node_count >= 2;
+        if multi_threaded && is_mimalloc(info.allocator_type) {
+            assert!(info.reason.contains("tuned for constrained memory"), "{}", info.reason);
+            let (_, reason) = get_recommended_allocator();
+            assert!(reason.contains("512GB constrained-memory threshold"), "{}", reason);
+        } else {
+            // Single-core hosts keep the system allocator, which needs no tuning
+            assert_eq!(info.allocator_type, AllocatorType::System);
+            assert!(!info.reason.contains("low-memory host"), "{}", info.reason);
+        }
+    }
+
+    #[test]
+    fn test_constrained_host_keeps_regular_selection() {
+        run_child(
+            "release::constrained_memory_child",
+            &[("AUTO_ALLOCATOR_LOW_MEMORY", "0"), ("AUTO_ALLOCATOR_CONSTRAINED_MEMORY", "512gb")],
+        );
+    }
+
+    #[test]
+    #[ignore = "runs in a child process"]
+    fn workload_on_low_memory_child() {
+        if std::env::var_os(CHILD_MARKER).is_none() {
+            return;
+        }
+        // The hint decides the backend; mimalloc still gets the low-overhead options
+        let info = get_allocator_info();
+        assert!(info.workload.is_some());
+        assert!(info.reason.contains("many-small-short-lived workload"), "{}", info.reason);
+    }
+
+    #[test]
+    fn test_workload_hint_wins_over_memory_class() {
+        run_child(
+            "release::workload_on_low_memory_child",
+            &[
+                ("AUTO_ALLOCATOR_LOW_MEMORY", "1024G"),
+                ("AUTO_ALLOCATOR_WORKLOAD", "many-small-short-lived"),
+            ],
+        );
+    }
+
+    #[test]
+    #[ignore = "runs in a child process"]
+    fn malformed_threshold_child() {
+        if std::env::var_os(CHILD_MARKER).is_none() {
+            return;
+        }
+        // An unreadable threshold falls back to the 1 GiB default instead of 1 byte
+        let info = get_allocator_info();
+        let effective = info.system_info.effective_memory_bytes;
+        if effective >= 1 << 30 {
+            assert!(!info.reason.contains("low-memory host"), "{}", info.reason);
+        }
+    }
+
+    #[test]
+    fn test_malformed_threshold_is_ignored() {
+        run_child("release::malformed_threshold_child", &[("AUTO_ALLOCATOR_LOW_MEMORY", "1 potato")]);
+    }
+}
@@ -0,0 +1,107 @@
+//! Memory budget tests for auto-allocator
+//!
+//! The budget is process-wide, so every test runs under a shared lock and
+//! sets limits relative to the current usage.
+#![cfg(feature = "memory-budget")]
+
+use auto_allocator::{
+    clear_memory_budget, memory_budget_status, on_soft_limit, set_memory_budget, BudgetStatus,
+    MemoryBudget,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, Once};
+
+static SERIAL: Mutex<()> = Mutex::new(());
+static SOFT_LIMIT_HITS: AtomicUsize = AtomicUsize::new(0);
+
+fn count_soft_limit_hit(_status: &BudgetStatus) {
+    SOFT_LIMIT_HITS.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Registers the counting callback once, however many tests need it
+fn register_hit_counter() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| assert!(on_soft_limit(count_soft_limit_hit)));
+}
+
+#[test]
+fn test_usage_is_tracked() {
+    let _serial = SERIAL.lock().unwrap();
+    let before = memory_budget_status().live_bytes;
+    let data: Vec<u8> = std::hint::black_box(Vec::with_capacity(1 << 20));
+    let during = memory_budget_status();
+    assert!(during.live_bytes >= before + (1 << 20) - (64 << 10));
+    assert!(during.peak_bytes >= during.live_bytes);
+    drop(data);
+}
+
+#[test]
+fn test_hard_limit_fails_allocation() {
+    let _serial = SERIAL.lock().unwrap();
+    let live = memory_budget_status().live_bytes;
+    set_memory_budget(MemoryBudget::new(u64::MAX, live + (4 << 20)));
+
+    let mut big: Vec<u8> = Vec::new();
+    let refused = big.try_reserve_exact(64 << 20).is_err();
+    let rejected = memory_budget_status().rejected_allocations;
+    let mut small: Vec<u8> = Vec::new();
+    let accepted = small.try_reserve_exact(1024).is_ok();
+
+    clear_memory_budget();
+    assert!(refused);
+    assert!(rejected >= 1);
+    assert!(accepted);
+}
+
+#[test]
+fn test_soft_limit_fires_callback_once() {
+    let _serial = SERIAL.lock().unwrap();
+    register_hit_counter();
+    let hits_before = SOFT_LIMIT_HITS.load(Ordering::SeqCst);
+
+    let live = memory_budget_status().live_bytes;
+    set_memory_budget(MemoryBudget::new(live + (1 << 20), u64::MAX));
+    let first: Vec<u8> = std::hint::black_box(Vec::with_capacity(2 << 20));
+    let second: Vec<u8> = std::hint::black_box(Vec::with_capacity(2 << 20));
+    let exceeded = memory_budget_status().soft_limit_exceeded;
+    drop(first);
+    drop(second);
+    clear_memory_budget();
+
+    assert!(exceeded);
+    assert_eq!(SOFT_LIMIT_HITS.load(Ordering::SeqCst) - hits_before, 1);
+}
+
+#[test]
+fn test_budget_from_system() {
+    let budget = MemoryBudget::from_system();
+    assert!(budget.soft_limit_bytes > 0);
+    assert!(budget.soft_limit_bytes < budget.hard_limit_bytes);
+}
+
+#[test]
+#[cfg(feature = "leak-report")]
+fn test_crossing_inside_another_hook_is_not_lost() {
+    let _serial = SERIAL.lock().unwrap();
+    register_hit_counter();
+    let hits_before = SOFT_LIMIT_HITS.load(Ordering::SeqCst);
+
+    // Building the leak report allocates inside the allocator's own hook, crossing the limit
+    // where no callback may run; usage then falls back to just under it
+    let path = std::env::temp_dir().join(format!("auto_allocator_budget_{}.json", std::process::id()));
+    let live = memory_budget_status().live_bytes;
+    set_memory_budget(MemoryBudget::new(live + 1024, u64::MAX));
+    let written = auto_allocator::write_leak_report(&path);
+    let hits_after_hook = SOFT_LIMIT_HITS.load(Ordering::SeqCst);
+
+    // The next allocation outside any hook still fires the callbacks
+    let data: Vec<u8> = std::hint::black_box(Vec::with_capacity(64 << 10));
+    let hits = SOFT_LIMIT_HITS.load(Ordering::SeqCst);
+    drop(data);
+    clear_memory_budget();
+    written.unwrap();
+    let _ = std::fs::remove_file(&path);
+
+    assert_eq!(hits_after_hook, hits_before);
+    assert_eq!(hits - hits_before, 1);
+}
@@ -0,0 +1,139 @@
+//! Memory pressure monitor tests for auto-allocator
+//!
+//! Real pressure cannot be induced reliably in CI, so the monitor is driven
+//! through its usage poller with thresholds every process already exceeds.
+//! A zero PSI window is rejected by the kernel, which keeps the poller in use
+//! on Linux too. Handlers only record events; assertions run on the test thread.
+#![cfg(feature = "memory-pressure")]
+
+use auto_allocator::{
+    on_memory_pressure, start_pressure_monitor, PressureEvent, PressureLevel, PressureMonitorConfig, PressureSource,
+};
+use std::sync::{Mutex, Once};
+use std::time::{Duration, Instant};
+
+static SERIAL: Mutex<()> = Mutex::new(());
+static EVENTS: Mutex<Vec<PressureEvent>> = Mutex::new(Vec::new());
+
+/// Registers the recording handler once for the whole test binary
+fn record_events() {
+    static REGISTER: Once = Once::new();
+    REGISTER.call_once(|| on_memory_pressure(|event| EVENTS.lock().unwrap().push(*event)));
+}
+
+/// Config whose poller fires on the first check without PSI triggers being armed
+fn poller_config(moderate_usage_percent: u8, critical_usage_percent: u8) -> PressureMonitorConfig {
+    PressureMonitorConfig {
+        window: Duration::ZERO,
+        poll_interval: Duration::from_millis(20),
+        moderate_usage_percent,
+        critical_usage_percent,
+        ..Default::default()
+    }
+}
+
+/// Runs a monitor until it reports a poller event and returns everything it reported
+fn run_until_poller_event(config: PressureMonitorConfig) -> Vec<PressureEvent> {
+    record_events();
+    EVENTS.lock().unwrap().clear();
+
+    let monitor = start_pressure_monitor(config).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline
+        && !EVENTS.lock().unwrap().iter().any(|event| event.source == PressureSource::Poller)
+    {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    monitor.stop();
+
+    std::mem::take(&mut *EVENTS.lock().unwrap())
+}
+
+#[test]
+fn test_monitor_starts_and_stops_promptly() {
+    let _serial = SERIAL.lock().unwrap();
+    let config = PressureMonitorConfig {
+        poll_interval: Duration::from_millis(50),
+        ..Default::default()
+    };
+    let monitor = start_pressure_monitor(config).unwrap();
+    std::thread::sleep(Duration::from_millis(120));
+
+    let stopping = Instant::now();
+    monitor.stop();
+    assert!(stopping.elapsed() < Duration::from_secs(2));
+}
+
+#[test]
+fn test_poller_reports_moderate_pressure() {
+    let _serial = SERIAL.lock().unwrap();
+    let events = run_until_poller_event(poller_config(0, 101));
+
+    let polled: Vec<_> = events.iter().filter(|event| event.source == PressureSource::Poller).collect();
+    // Edge-triggered: usage stays above the threshold, so the level is reported once
+    assert_eq!(polled.len(), 1, "{:?}", events);
+    assert_eq!(polled[0].level, PressureLevel::Moderate);
+}
+
+#[test]
+fn test_poller_reports_critical_pressure() {
+    let _serial = SERIAL.lock().unwrap();
+    let events = run_until_poller_event(poller_config(0, 0));
+
+    let polled: Vec<_> = events.iter().filter(|event| event.source == PressureSource::Poller).collect();
+    assert_eq!(polled.len(), 1, "{:?}", events);
+    assert_eq!(polled[0].level, PressureLevel::Critical);
+}
+
+/// Resident set size of this process in bytes
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn resident_bytes() -> usize {
+    let statm = std::fs::read_to_string("/proc/self/statm").unwrap();
+    let pages: usize = statm.split_whitespace().nth(1).unwrap().parse().unwrap();
+    pages * unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn child_purge_releases_memory() {
+    if std::env::var_os("PRESSURE_TEST_CHILD").is_none() {
+        return;
+    }
+    // Blocks below mmap thresholds stay in the allocator's own heap; the last one stays live
+    // so that freeing the others cannot trim the heap on its own
+    let mut blocks: Vec<Vec<u8>> = (0..256).map(|i| vec![i as u8; 64 << 10]).collect();
+    let pinned = blocks.pop();
+    drop(std::hint::black_box(blocks));
+
+    let before = resident_bytes();
+    auto_allocator::purge_allocator_caches();
+    let after = resident_bytes();
+    drop(pinned);
+
+    assert!(before.saturating_sub(after) >= 8 << 20, "resident before {} after {}", before, after);
+}
+
+#[test]
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+fn test_purge_allocator_caches_releases_memory() {
+    let output = std::process::Command::new(std::env::current_exe().unwrap())
+        .args(["child_purge_releases_memory", "--exact", "--ignored", "--test-threads=1"])
+        .env("PRESSURE_TEST_CHILD", "1")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_default_config_is_consistent() {
+    let config = PressureMonitorConfig::default();
+    assert!(config.moderate_usage_percent < config.critical_usage_percent);
+    assert!(config.critical_usage_percent <= 100);
+    assert!(config.window.as_secs() % 2 == 0);
+}
@@ -0,0 +1,45 @@
+//! NUMA topology tests for auto-allocator
+//!
+//! Detection is compared against the node directories in sysfs, which the
+//! crate itself reads through the allocation-free `online` list.
+
+use auto_allocator::{get_allocator_info, NumaNode};
+
+#[test]
+fn test_numa_node_count_matches_nodes() {
+    let system = &get_allocator_info().system_info;
+    assert!(system.numa_node_count >= 1);
+    if !system.numa_nodes.is_empty() {
+        assert_eq!(system.numa_node_count, system.numa_nodes.len());
+    }
+    assert!(system.numa_nodes.windows(2).all(|pair| pair[0].id < pair[1].id));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_numa_nodes_match_sysfs() {
+    let system = &get_allocator_info().system_info;
+    let Ok(entries) = std::fs::read_dir("/sys/devices/system/node") else {
+        assert!(system.numa_nodes.is_empty());
+        return;
+    };
+    let mut node_dirs: Vec<usize> = entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.strip_prefix("node")?.parse().ok())
+        .collect();
+    node_dirs.sort_unstable();
+
+    let ids: Vec<usize> = system.numa_nodes.iter().map(|node: &NumaNode| node.id).collect();
+    assert!(ids.iter().all(|id| node_dirs.contains(id)), "{:?} vs {:?}", ids, node_dirs);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_numa_node_memory_fits_total() {
+    let system = &get_allocator_info().system_info;
+    let node_total: u64 = system.numa_nodes.iter().map(|node| node.total_memory_bytes).sum();
+    if !system.numa_nodes.is_empty() {
+        assert!(node_total > 0);
+        // Per-node totals add up to the global total, give or take memory reserved at boot
+        assert!(node_total <= system.total_memory_bytes + (64 << 20), "{} > {}", node_total, system.total_memory_bytes);
+    }
+}
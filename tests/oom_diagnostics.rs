@@ -0,0 +1,125 @@
+//! Out-of-memory diagnostics tests for auto-allocator
+//!
+//! A request far beyond any real address space makes the backend return
+//! null. Records are rate-limited per process, so each scenario runs in a
+//! child process and its records are read back from the child's stderr.
+#![cfg(all(feature = "oom-diagnostics", unix))]
+
+use std::process::Command;
+
+const CHILD_MARKER: &str = "AUTO_ALLOCATOR_OOM_TEST_CHILD";
+
+/// Runs `oom_child` with the given scenario and returns the records it wrote
+fn run_child(scenario: &str) -> Vec<String> {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["oom_child", "--exact", "--ignored", "--test-threads=1"])
+        .env(CHILD_MARKER, scenario)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter(|line| line.starts_with("{\"event\""))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn oom_child() {
+    let Ok(scenario) = std::env::var(CHILD_MARKER) else {
+        return;
+    };
+    match scenario.as_str() {
+        "backend" => {
+            let mut huge: Vec<u8> = Vec::new();
+            assert!(huge.try_reserve_exact(1 << 62).is_err());
+        }
+        "repeated" => {
+            let before = auto_allocator::allocation_failure_count();
+            for _ in 0..100 {
+                let mut huge: Vec<u8> = Vec::new();
+                assert!(huge.try_reserve_exact(1 << 62).is_err());
+            }
+            assert_eq!(auto_allocator::allocation_failure_count(), before + 100);
+        }
+        #[cfg(feature = "memory-budget")]
+        "budget" => {
+            use auto_allocator::{clear_memory_budget, memory_budget_status, set_memory_budget, MemoryBudget};
+
+            let hard_limit = memory_budget_status().live_bytes + (4 << 20);
+            set_memory_budget(MemoryBudget::new(u64::MAX, hard_limit));
+            let mut big: Vec<u8> = Vec::new();
+            let refused = big.try_reserve_exact(64 << 20).is_err();
+            clear_memory_budget();
+            assert!(refused);
+        }
+        other => panic!("unknown scenario {}", other),
+    }
+}
+
+#[test]
+fn test_failed_allocation_writes_record() {
+    let records = run_child("backend");
+    assert_eq!(records.len(), 1, "{:?}", records);
+    let record = &records[0];
+
+    // A plain JSON line: the failure was handed back to the caller, so nothing claims it is fatal
+    assert!(record.starts_with("{\"event\":\"allocation_failed\",\"cause\":\"backend\","), "{}", record);
+    assert!(!record.contains("FATAL"));
+    assert!(record.contains(&format!("\"size\":{}", 1u64 << 62)));
+    assert!(record.contains("\"align\":1"));
+    assert!(record.contains("\"backend\":\""));
+    assert!(record.contains("\"failures\":1,"));
+    assert!(record.contains(&format!("\"os_type\":\"{}\"", std::env::consts::OS)));
+    assert!(record.ends_with("}\n"));
+}
+
+#[test]
+fn test_repeated_failures_are_rate_limited() {
+    // Fallible code retrying in a loop must not flood stderr
+    let records = run_child("repeated");
+    assert_eq!(records.len(), 1, "{:?}", records);
+}
+
+#[test]
+#[cfg(feature = "memory-budget")]
+fn test_budget_rejection_writes_record() {
+    let records = run_child("budget");
+    assert_eq!(records.len(), 1, "{:?}", records);
+    let record = &records[0];
+
+    assert!(
+        record.starts_with("{\"event\":\"allocation_failed\",\"cause\":\"budget_hard_limit\","),
+        "{}",
+        record
+    );
+    assert!(record.contains(&format!("\"size\":{}", 64 << 20)));
+    assert!(record.contains("\"hard_limit_bytes\":"));
+}
+
+#[test]
+fn test_records_follow_the_redirected_descriptor() {
+    use std::io::{Read, Seek, SeekFrom};
+    use std::os::unix::io::AsRawFd;
+
+    let path = std::env::temp_dir().join(format!("auto_allocator_oom_{}.jsonl", std::process::id()));
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .read(true)
+        .write(true)
+        .open(&path)
+        .unwrap();
+    auto_allocator::set_oom_diagnostics_fd(file.as_raw_fd());
+    let mut huge: Vec<u8> = Vec::new();
+    assert!(huge.try_reserve_exact(1 << 62).is_err());
+    auto_allocator::set_oom_diagnostics_fd(2);
+
+    let mut records = String::new();
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.read_to_string(&mut records).unwrap();
+    let _ = std::fs::remove_file(&path);
+    // The only failure of this process, so it is not rate-limited
+    assert!(records.starts_with("{\"event\":\"allocation_failed\""), "{}", records);
+}
@@ -16,6 +16,7 @@ fn test_allocator_selection_consistency() {
 }
 
 #[test]
+#[cfg(not(auto_allocator_forced))]
 fn test_platform_specific_allocator_selection() {
     let info = get_allocator_info();
 
@@ -0,0 +1,82 @@
+//! Use-after-free quarantine tests for auto-allocator
+//!
+//! The quarantine is process-wide, so tests share a lock; detected corruption
+//! aborts, so that scenario re-runs this binary as a child process.
+#![cfg(feature = "quarantine")]
+
+use auto_allocator::{flush_quarantine, quarantine_stats, set_quarantine_size, QUARANTINE_POISON};
+use std::alloc::{alloc, dealloc, Layout};
+use std::process::Command;
+use std::sync::Mutex;
+
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[test]
+fn test_freed_blocks_are_poisoned_and_held() {
+    let _serial = SERIAL.lock().unwrap();
+    flush_quarantine();
+    let released_before = quarantine_stats().released_blocks;
+
+    let layout = Layout::from_size_align(1000, 8).unwrap();
+    unsafe {
+        let ptr = alloc(layout);
+        std::ptr::write_bytes(ptr, 0x11, layout.size());
+        dealloc(ptr, layout);
+        // Still owned by the quarantine, so the read is of poisoned memory
+        assert_eq!(std::ptr::read_volatile(ptr.add(500)), QUARANTINE_POISON);
+    }
+
+    let stats = quarantine_stats();
+    assert!(stats.blocks >= 1);
+    assert!(stats.bytes >= layout.size());
+
+    flush_quarantine();
+    let stats = quarantine_stats();
+    assert_eq!(stats.blocks, 0);
+    assert!(stats.released_blocks > released_before);
+}
+
+#[test]
+fn test_quarantine_respects_byte_limit() {
+    let _serial = SERIAL.lock().unwrap();
+    set_quarantine_size(4096);
+    flush_quarantine();
+
+    let large: Vec<u8> = std::hint::black_box(vec![1; 8192]);
+    drop(large);
+    for _ in 0..16 {
+        drop(std::hint::black_box(vec![2u8; 1024]));
+    }
+    assert!(quarantine_stats().bytes <= 4096);
+
+    set_quarantine_size(16 * 1024 * 1024);
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn child_write_after_free() {
+    if std::env::var_os("QUARANTINE_TEST_CHILD").is_none() {
+        return;
+    }
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    unsafe {
+        let ptr = alloc(layout);
+        dealloc(ptr, layout);
+        std::ptr::write_volatile(ptr.add(10), 0x42);
+    }
+    flush_quarantine();
+}
+
+#[test]
+fn test_write_after_free_is_reported() {
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["child_write_after_free", "--exact", "--ignored", "--test-threads=1"])
+        .env("QUARANTINE_TEST_CHILD", "1")
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(stderr.contains("use-after-free write detected"), "stderr: {}", stderr);
+    assert!(stderr.contains("(size 64, align 8): byte 10 is 0x42"), "stderr: {}", stderr);
+}
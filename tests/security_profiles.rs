@@ -0,0 +1,155 @@
+//! Security profile tests for auto-allocator
+//!
+//! The profile is read once, before the first allocation, so each scenario runs
+//! in a child process started with its own `AUTO_ALLOCATOR_PROFILE`.
+#![cfg(all(feature = "security-profiles", unix))]
+
+use auto_allocator::{get_allocator_info, AllocatorType, SecurityProfile};
+use std::alloc::{alloc, dealloc, Layout};
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Command, Output};
+
+const CHILD_MARKER: &str = "SECURITY_PROFILE_TEST_CHILD";
+
+fn run_child(test: &str, profile: &str) -> Output {
+    Command::new(std::env::current_exe().unwrap())
+        .args([test, "--exact", "--ignored", "--test-threads=1"])
+        .env(CHILD_MARKER, "1")
+        .env("AUTO_ALLOCATOR_PROFILE", profile)
+        .output()
+        .unwrap()
+}
+
+fn is_child() -> bool {
+    std::env::var_os(CHILD_MARKER).is_some()
+}
+
+fn expected_profile() -> SecurityProfile {
+    match std::env::var("AUTO_ALLOCATOR_PROFILE").unwrap().as_str() {
+        "performance" => SecurityProfile::Performance,
+        "balanced" => SecurityProfile::Balanced,
+        "hardened" => SecurityProfile::Hardened,
+        _ => SecurityProfile::Paranoid,
+    }
+}
+
+/// Frees a filled block and returns what is left in it, past the backend's own metadata
+fn bytes_after_free() -> Vec<u8> {
+    let layout = Layout::from_size_align(4096, 8).unwrap();
+    let mut left = vec![0u8; layout.size()];
+    unsafe {
+        let ptr = alloc(layout);
+        std::ptr::write_bytes(ptr, 0x5A, layout.size());
+        dealloc(ptr, layout);
+        for (offset, byte) in left.iter_mut().enumerate() {
+            *byte = std::ptr::read_volatile(ptr.add(offset));
+        }
+    }
+    left.split_off(32)
+}
+
+/// Value the quarantine leaves behind instead, when it holds freed blocks
+#[cfg(feature = "quarantine")]
+fn freed_byte(_profile_byte: u8) -> u8 {
+    auto_allocator::QUARANTINE_POISON
+}
+
+#[cfg(not(feature = "quarantine"))]
+fn freed_byte(profile_byte: u8) -> u8 {
+    profile_byte
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn child_profile_is_reported() {
+    if !is_child() {
+        return;
+    }
+    let info = get_allocator_info();
+    let profile = expected_profile();
+    let features = info.security_features;
+
+    assert_eq!(info.security_profile, profile);
+    assert_eq!(features.secure_mimalloc, info.allocator_type == AllocatorType::MimallocSecure);
+    assert_eq!(
+        features.free_poisoning,
+        profile == SecurityProfile::Hardened || cfg!(feature = "quarantine")
+    );
+    // A wipe that poisoning overwrites afterwards is not in effect
+    assert_eq!(
+        features.zero_on_free,
+        !features.free_poisoning && (profile == SecurityProfile::Paranoid || cfg!(feature = "zero-on-free"))
+    );
+    assert_eq!(features.large_guard_pages, profile >= SecurityProfile::Hardened);
+    assert_eq!(features.randomized_allocation, info.allocator_type == AllocatorType::MimallocSecure);
+
+    // Selection only uses the mimalloc build the profile asks for
+    if profile == SecurityProfile::Performance {
+        assert_ne!(info.allocator_type, AllocatorType::MimallocSecure, "{}", info.reason);
+    } else {
+        assert_ne!(info.allocator_type, AllocatorType::Mimalloc, "{}", info.reason);
+    }
+    if info.reason.contains("security profile rules out") {
+        assert_eq!(info.allocator_type, AllocatorType::System);
+    }
+}
+
+#[test]
+fn test_profile_is_chosen_at_startup() {
+    for profile in ["performance", "balanced", "hardened", "paranoid"] {
+        let output = run_child("child_profile_is_reported", profile);
+        assert!(output.status.success(), "{}: {}", profile, String::from_utf8_lossy(&output.stderr));
+    }
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn child_hardened_poisons_freed_blocks() {
+    if !is_child() {
+        return;
+    }
+    let poison = freed_byte(auto_allocator::FREE_POISON);
+    assert!(bytes_after_free().iter().all(|&byte| byte == poison));
+}
+
+#[test]
+fn test_hardened_poisons_freed_blocks() {
+    let output = run_child("child_hardened_poisons_freed_blocks", "hardened");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn child_paranoid_wipes_freed_blocks() {
+    if !is_child() {
+        return;
+    }
+    let wiped = freed_byte(0);
+    assert!(bytes_after_free().iter().all(|&byte| byte == wiped));
+}
+
+#[test]
+fn test_paranoid_wipes_freed_blocks() {
+    let output = run_child("child_paranoid_wipes_freed_blocks", "paranoid");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
+#[test]
+#[ignore = "runs in a child process"]
+fn child_large_overflow() {
+    if !is_child() {
+        return;
+    }
+    let buffer = vec![0u8; 128 * 1024];
+    let end = unsafe { buffer.as_ptr().add(buffer.len()) as *mut u8 };
+    // Any redzone before the guard page is far shorter than this
+    for offset in 0..64 {
+        unsafe { std::ptr::write_volatile(end.add(offset), 1) };
+    }
+}
+
+#[test]
+fn test_hardened_large_overflow_faults() {
+    let output = run_child("child_large_overflow", "hardened");
+    assert!(output.status.signal().is_some(), "status: {:?}", output.status);
+}
@@ -0,0 +1,225 @@
+//! Selection cache tests for auto-allocator
+//!
+//! Each test points `AUTO_ALLOCATOR_CACHE_DIR` (or, for the default location,
+//! `XDG_CACHE_HOME` and `HOME`) at its own temporary directory.
+//! Reusing a decision happens at startup, so those scenarios run in child
+//! processes and only in release builds, where runtime selection takes place.
+#![cfg(all(feature = "selection-cache", unix, not(auto_allocator_forced)))]
+
+use auto_allocator::{
+    get_recommended_allocator, invalidate_selection_cache, selection_cache_status, AllocatorType,
+    CacheState,
+};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+static SERIAL: Mutex<()> = Mutex::new(());
+
+fn cache_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("auto-allocator-cache-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::env::set_var("AUTO_ALLOCATOR_CACHE_DIR", &dir);
+    std::env::remove_var("AUTO_ALLOCATOR_CACHE");
+    dir
+}
+
+fn write_entry(fingerprint: u64, allocator_id: u8) -> PathBuf {
+    let path = selection_cache_status().path.unwrap();
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(
+        &path,
+        format!("auto-allocator-selection-v2 fingerprint={:016x} allocator={}\n", fingerprint, allocator_id),
+    )
+    .unwrap();
+    path
+}
+
+#[test]
+fn test_empty_cache_is_missing() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = cache_dir("missing");
+
+    let status = selection_cache_status();
+    assert_eq!(status.state, CacheState::Missing);
+    assert!(status.path.unwrap().starts_with(&dir));
+    assert_ne!(status.fingerprint, 0);
+    assert_eq!(status.cached_allocator, None);
+}
+
+#[test]
+fn test_matching_fingerprint_is_valid() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = cache_dir("valid");
+
+    write_entry(selection_cache_status().fingerprint, 1);
+    let status = selection_cache_status();
+    assert_eq!(status.state, CacheState::Valid);
+    assert_eq!(status.cached_allocator, Some(AllocatorType::System));
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_changed_fingerprint_is_stale() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = cache_dir("stale");
+
+    write_entry(selection_cache_status().fingerprint ^ 1, 1);
+    let status = selection_cache_status();
+    assert_eq!(status.state, CacheState::Stale);
+    assert_eq!(status.cached_allocator, Some(AllocatorType::System));
+
+    let (_, reason) = get_recommended_allocator();
+    assert!(reason.contains("stale"), "{}", reason);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_unreadable_entry_is_corrupt() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = cache_dir("corrupt");
+
+    let path = write_entry(0, 1);
+    std::fs::write(&path, "not a cache file").unwrap();
+    assert_eq!(selection_cache_status().state, CacheState::Corrupt);
+
+    write_entry(selection_cache_status().fingerprint, 9);
+    assert_eq!(selection_cache_status().state, CacheState::Corrupt);
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_invalidate_removes_entry() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let dir = cache_dir("invalidate");
+
+    let path = write_entry(selection_cache_status().fingerprint, 1);
+    invalidate_selection_cache().unwrap();
+    assert!(!path.exists());
+    assert_eq!(selection_cache_status().state, CacheState::Missing);
+
+    // Invalidating an empty cache is not an error
+    invalidate_selection_cache().unwrap();
+    let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn test_cache_can_be_disabled() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    cache_dir("disabled");
+    std::env::set_var("AUTO_ALLOCATOR_CACHE", "off");
+
+    let status = selection_cache_status();
+    std::env::remove_var("AUTO_ALLOCATOR_CACHE");
+    assert_eq!(status.state, CacheState::Disabled);
+    assert_eq!(status.path, None);
+}
+
+#[test]
+fn test_default_directory_follows_xdg_then_home() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let root = cache_dir("default");
+    std::env::remove_var("AUTO_ALLOCATOR_CACHE_DIR");
+    let xdg = std::env::var_os("XDG_CACHE_HOME");
+    let home = std::env::var_os("HOME");
+
+    std::env::set_var("XDG_CACHE_HOME", root.join("xdg"));
+    let with_xdg = selection_cache_status().path;
+    std::env::remove_var("XDG_CACHE_HOME");
+    std::env::set_var("HOME", root.join("home"));
+    let with_home = selection_cache_status().path;
+
+    match xdg {
+        Some(xdg) => std::env::set_var("XDG_CACHE_HOME", xdg),
+        None => std::env::remove_var("XDG_CACHE_HOME"),
+    }
+    match home {
+        Some(home) => std::env::set_var("HOME", home),
+        None => std::env::remove_var("HOME"),
+    }
+    assert_eq!(with_xdg.unwrap().parent(), Some(root.join("xdg/auto-allocator").as_path()));
+    assert_eq!(with_home.unwrap().parent(), Some(root.join("home/.cache/auto-allocator").as_path()));
+}
+
+#[cfg(not(debug_assertions))]
+mod startup {
+    use super::*;
+    use std::process::Command;
+
+    const CHILD_MARKER: &str = "AUTO_ALLOCATOR_CACHE_TEST_CHILD";
+
+    /// Runs `cache_child` with the given environment, which checks `expect_cached` at startup
+    fn run_child(dir: &PathBuf, mode: &str, expect_cached: bool) {
+        let path = selection_cache_status().path.unwrap();
+        let calibrated = std::fs::read_to_string(path).is_ok_and(|entry| entry.contains(" timing="));
+        let expected = match (expect_cached, calibrated) {
+            (true, true) => "cached-calibration",
+            (true, false) => "cached",
+            (false, _) => "selected",
+        };
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args(["startup::cache_child", "--exact", "--ignored", "--test-threads=1"])
+            .env(CHILD_MARKER, expected)
+            .env("AUTO_ALLOCATOR_CACHE_DIR", dir)
+            .env("AUTO_ALLOCATOR_CACHE", mode)
+            .status()
+            .unwrap();
+        assert!(status.success(), "child expecting cached={} failed", expect_cached);
+    }
+
+    #[test]
+    #[ignore = "runs in a child process"]
+    fn cache_child() {
+        let Ok(expected) = std::env::var(CHILD_MARKER) else {
+            return;
+        };
+        let status = selection_cache_status();
+        let info = auto_allocator::get_allocator_info();
+        assert_eq!(status.used_at_startup, expected.starts_with("cached"));
+        if status.used_at_startup {
+            assert_eq!(status.cached_allocator, Some(info.allocator_type));
+            assert!(info.reason.contains("selected from cached"), "{}", info.reason);
+        }
+        // Measurements saved with the decision are reported again by the run that reuses it
+        if expected == "cached-calibration" {
+            let calibration = info.calibration.as_ref().expect("cached calibration was not restored");
+            assert_eq!(calibration.timings[0].allocator_type, info.allocator_type);
+            assert!(info.reason.contains("cached adaptive calibration"), "{}", info.reason);
+        }
+    }
+
+    #[test]
+    fn test_second_run_reuses_decision() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = cache_dir("reuse");
+
+        run_child(&dir, "read-write", false);
+        assert_eq!(selection_cache_status().state, CacheState::Valid);
+        run_child(&dir, "read-write", true);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_read_only_never_writes() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = cache_dir("read-only");
+
+        run_child(&dir, "read-only", false);
+        assert_eq!(selection_cache_status().state, CacheState::Missing);
+
+        // An existing valid entry is still used
+        write_entry(selection_cache_status().fingerprint, 1);
+        run_child(&dir, "read-only", true);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_stale_entry_is_replaced() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = cache_dir("replace");
+
+        write_entry(selection_cache_status().fingerprint ^ 1, 1);
+        run_child(&dir, "read-write", false);
+        assert_eq!(selection_cache_status().state, CacheState::Valid);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
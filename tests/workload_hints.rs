@@ -0,0 +1,86 @@
+//! Workload hint tests for auto-allocator
+//!
+//! This test binary declares a workload with declare_workload!, so the hint is
+//! in place before its first allocation. Debug builds select at compile time
+//! and never consult it; the environment override runs in a child process.
+#![cfg(all(not(target_os = "none"), not(auto_allocator_forced)))]
+
+use auto_allocator::{declare_workload, get_allocator_info, set_workload, Workload};
+use std::sync::Mutex;
+
+declare_workload!(Workload::ManySmallShortLived);
+
+static SERIAL: Mutex<()> = Mutex::new(());
+
+#[test]
+fn test_hint_after_selection_is_too_late() {
+    let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = get_allocator_info();
+    assert!(!set_workload(Workload::ManySmallShortLived));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_debug_builds_ignore_hint() {
+    let info = get_allocator_info();
+    assert_eq!(info.workload, None);
+    assert!(info.reason.contains("debug"), "{}", info.reason);
+}
+
+#[cfg(not(debug_assertions))]
+mod release {
+    use super::*;
+    use auto_allocator::AllocatorType;
+    use std::process::Command;
+
+    const CHILD_MARKER: &str = "AUTO_ALLOCATOR_WORKLOAD_TEST_CHILD";
+
+    fn is_mimalloc(allocator_type: AllocatorType) -> bool {
+        matches!(allocator_type, AllocatorType::Mimalloc | AllocatorType::MimallocSecure)
+    }
+
+    #[test]
+    fn test_declared_hint_decides_selection() {
+        let info = get_allocator_info();
+        assert_eq!(info.workload, Some(Workload::ManySmallShortLived));
+        // Small short-lived objects favour mimalloc even on a single core
+        assert!(is_mimalloc(info.allocator_type), "{:?}", info.allocator_type);
+        assert!(info.reason.contains("many-small-short-lived workload"), "{}", info.reason);
+    }
+
+    #[test]
+    fn test_recommendation_follows_hint() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let (recommended, reason) = auto_allocator::get_recommended_allocator();
+        assert!(is_mimalloc(recommended), "{:?}", recommended);
+        assert!(reason.contains("many-small-short-lived workload"), "{}", reason);
+    }
+
+    #[test]
+    #[ignore = "runs in a child process"]
+    fn environment_child() {
+        if std::env::var_os(CHILD_MARKER).is_none() {
+            return;
+        }
+        let info = get_allocator_info();
+        assert_eq!(info.workload, Some(Workload::LowFootprint));
+
+        let small_host = info.system_info.cpu_cores <= 4 || info.system_info.total_memory_bytes < 4 << 30;
+        if small_host {
+            assert_eq!(info.allocator_type, AllocatorType::System);
+        } else {
+            assert!(is_mimalloc(info.allocator_type), "{:?}", info.allocator_type);
+        }
+    }
+
+    #[test]
+    fn test_environment_overrides_declared_hint() {
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args(["release::environment_child", "--exact", "--ignored", "--test-threads=1"])
+            .env(CHILD_MARKER, "1")
+            .env("AUTO_ALLOCATOR_WORKLOAD", "low-footprint")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+}
@@ -0,0 +1,55 @@
+//! Zero-on-free tests for auto-allocator
+//!
+//! Freed blocks are inspected right after deallocation. The backend may reuse
+//! the first words of a free chunk for its own metadata, so those are skipped.
+#![cfg(feature = "zero-on-free")]
+
+use auto_allocator as _;
+use std::alloc::{alloc, dealloc, realloc, Layout};
+use std::sync::Mutex;
+
+static SERIAL: Mutex<()> = Mutex::new(());
+
+/// Bytes at the start of a freed chunk the backend may overwrite with free-list pointers
+const BACKEND_METADATA: usize = 32;
+
+/// Value left in freed memory: zeros, or the poison pattern when the quarantine holds it
+fn freed_byte() -> u8 {
+    #[cfg(feature = "quarantine")]
+    return auto_allocator::QUARANTINE_POISON;
+    #[cfg(not(feature = "quarantine"))]
+    return 0;
+}
+
+unsafe fn assert_wiped(ptr: *const u8, len: usize) {
+    for offset in BACKEND_METADATA..len {
+        assert_eq!(std::ptr::read_volatile(ptr.add(offset)), freed_byte(), "byte {}", offset);
+    }
+}
+
+#[test]
+fn test_dealloc_wipes_block() {
+    let _serial = SERIAL.lock().unwrap();
+    let layout = Layout::from_size_align(4096, 8).unwrap();
+    unsafe {
+        let ptr = alloc(layout);
+        std::ptr::write_bytes(ptr, 0x5A, layout.size());
+        dealloc(ptr, layout);
+        assert_wiped(ptr, layout.size());
+    }
+}
+
+#[test]
+fn test_realloc_wipes_old_block() {
+    let _serial = SERIAL.lock().unwrap();
+    let layout = Layout::from_size_align(256, 8).unwrap();
+    unsafe {
+        let old = alloc(layout);
+        std::ptr::write_bytes(old, 0xC3, layout.size());
+        let new = realloc(old, layout, 64 * 1024);
+        assert_ne!(new, old);
+        assert!(std::slice::from_raw_parts(new, layout.size()).iter().all(|&b| b == 0xC3));
+        assert_wiped(old, layout.size());
+        dealloc(new, Layout::from_size_align(64 * 1024, 8).unwrap());
+    }
+}